@@ -0,0 +1,245 @@
+use std::{
+    fmt::{self, Display},
+    fs,
+    io::{self, Write},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+/// Grid of characters, first row on top
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Frame {
+    rows: Vec<Vec<char>>,
+}
+
+impl Frame {
+    /// Creates a frame by calling `cell` with the `(column, row)` of each cell
+    pub fn from_fn(
+        width: usize,
+        height: usize,
+        mut cell: impl FnMut((usize, usize)) -> char,
+    ) -> Self {
+        Self {
+            rows: (0..height)
+                .map(|row| (0..width).map(|col| cell((col, row))).collect())
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn get(&self, (col, row): (usize, usize)) -> Option<char> {
+        self.rows.get(row)?.get(col).copied()
+    }
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.rows {
+            for c in row {
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Window of fixed size over a world of cells, where `y` grows upward
+///
+/// The window moves only when a followed point comes closer than `margin` cells to one of its edges.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Viewport {
+    width: usize,
+    height: usize,
+    margin: usize,
+    /// World coordinates of the top-left cell
+    left: i64,
+    top: i64,
+}
+
+impl Viewport {
+    /// Viewport with its bottom-left cell at the world origin
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "viewport must not be empty");
+        Self {
+            width,
+            height,
+            margin: 0,
+            left: 0,
+            top: height as i64 - 1,
+        }
+    }
+
+    pub fn with_margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Viewport with its bottom-left cell at the given world coordinates
+    pub fn with_origin(mut self, (x, y): (i64, i64)) -> Self {
+        self.left = x;
+        self.top = y + self.height as i64 - 1;
+        self
+    }
+
+    /// Viewport centered on the given point
+    pub fn centered_on(mut self, (x, y): (i64, i64)) -> Self {
+        self.left = x - (self.width as i64 - 1) / 2;
+        self.top = y + (self.height as i64 - 1) / 2;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// World coordinates of the bottom-left cell
+    pub fn origin(&self) -> (i64, i64) {
+        (self.left, self.top - (self.height as i64 - 1))
+    }
+
+    /// Moves the viewport as little as possible to keep `point` at least `margin` cells away from the edges
+    pub fn follow(&mut self, (x, y): (i64, i64)) {
+        let margin_x = self.margin.min((self.width - 1) / 2) as i64;
+        let margin_y = self.margin.min((self.height - 1) / 2) as i64;
+        let (right, bottom) = (
+            self.left + self.width as i64 - 1,
+            self.top - (self.height as i64 - 1),
+        );
+        if x < self.left + margin_x {
+            self.left = x - margin_x;
+        } else if x > right - margin_x {
+            self.left += x - (right - margin_x);
+        }
+        if y > self.top - margin_y {
+            self.top = y + margin_y;
+        } else if y < bottom + margin_y {
+            self.top -= bottom + margin_y - y;
+        }
+    }
+
+    /// Position of the world `point` in the frame, if visible
+    pub fn project(&self, (x, y): (i64, i64)) -> Option<(usize, usize)> {
+        let col = usize::try_from(x - self.left).ok()?;
+        let row = usize::try_from(self.top - y).ok()?;
+        (col < self.width && row < self.height).then_some((col, row))
+    }
+
+    /// Renders the visible cells, calling `cell` with the world coordinates of each cell
+    pub fn render(&self, mut cell: impl FnMut((i64, i64)) -> char) -> Frame {
+        Frame::from_fn(self.width, self.height, |(col, row)| {
+            cell((self.left + col as i64, self.top - row as i64))
+        })
+    }
+}
+
+/// Plays the `frames` in a terminal, waiting `delay` between each frame
+///
+/// Each frame is drawn over the previous one by moving the cursor back to the top-left corner of the screen.
+pub fn play(
+    frames: impl IntoIterator<Item = Frame>,
+    mut out: impl Write,
+    delay: Duration,
+) -> io::Result<()> {
+    write!(out, "\x1b[?25l\x1b[2J")?;
+    let result = frames.into_iter().try_for_each(|frame| {
+        write!(out, "\x1b[H{frame}")?;
+        out.flush()?;
+        thread::sleep(delay);
+        Ok(())
+    });
+    write!(out, "\x1b[?25h")?;
+    out.flush()?;
+    result
+}
+
+/// Writes each frame in its own text file (`frame-00000.txt`, `frame-00001.txt`, ...) in `dir`
+///
+/// Returns the number of written frames.
+pub fn dump(frames: impl IntoIterator<Item = Frame>, dir: impl AsRef<Path>) -> io::Result<usize> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut count = 0;
+    for frame in frames {
+        fs::write(dir.join(format!("frame-{count:05}.txt")), frame.to_string())?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_render_world_with_y_up() {
+        let viewport = Viewport::new(3, 2);
+        let frame = viewport.render(|(x, y)| char::from_digit((x + 3 * y) as u32, 10).unwrap());
+        assert_eq!(frame.to_string(), "345\n012\n");
+        assert_eq!(viewport.origin(), (0, 0));
+        assert_eq!(viewport.project((2, 1)), Some((2, 0)));
+        assert_eq!(viewport.project((3, 0)), None);
+        assert_eq!(viewport.project((0, -1)), None);
+    }
+
+    #[rstest]
+    #[case((2, 2), (0, 0))]
+    #[case((3, 2), (0, 0))]
+    #[case((4, 2), (1, 0))]
+    #[case((-1, 2), (-2, 0))]
+    #[case((2, 5), (0, 2))]
+    #[case((2, -3), (0, -4))]
+    fn should_follow_point_with_margin(
+        #[case] point: (i64, i64),
+        #[case] expected_origin: (i64, i64),
+    ) {
+        let mut viewport = Viewport::new(5, 5).with_margin(1);
+        viewport.follow(point);
+        assert_eq!(viewport.origin(), expected_origin);
+        let (col, row) = viewport.project(point).unwrap();
+        assert!((1..4).contains(&col) && (1..4).contains(&row));
+    }
+
+    #[test]
+    fn should_center_viewport() {
+        let viewport = Viewport::new(5, 3).centered_on((10, -10));
+        assert_eq!(viewport.origin(), (8, -11));
+        assert_eq!(viewport.project((10, -10)), Some((2, 1)));
+        assert_eq!(viewport.with_origin((-1, 2)).origin(), (-1, 2));
+    }
+
+    #[test]
+    fn should_play_frames_with_ansi_cursor_control() {
+        let frames =
+            ["ab", "cd"].map(|s| Frame::from_fn(2, 1, |(col, _)| s.chars().nth(col).unwrap()));
+        let mut out = Vec::new();
+        play(frames, &mut out, Duration::ZERO).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[?25l\x1b[2J\x1b[Hab\n\x1b[Hcd\n\x1b[?25h"
+        );
+    }
+
+    #[test]
+    fn should_dump_frames_to_directory() {
+        let dir = std::env::temp_dir().join(format!("aoc-animation-{}", std::process::id()));
+        let frames = (0..3).map(|i| Frame::from_fn(1, 1, |_| char::from_digit(i, 10).unwrap()));
+        let count = dump(frames, &dir).unwrap();
+        let last = fs::read_to_string(dir.join("frame-00002.txt")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(last, "2\n");
+    }
+}
@@ -0,0 +1,251 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// Quantity accumulated along a simulation, such as a tower height or inspection counts
+pub trait Additive: Clone {
+    /// Value reached after repeating `times` more the progression from `start` to `end`,
+    /// or `None` if it overflows
+    fn add_cycles(&self, start: &Self, end: &Self, times: u64) -> Option<Self>;
+}
+
+macro_rules! impl_additive {
+    ($($t:ty),*) => {
+        $(impl Additive for $t {
+            fn add_cycles(&self, start: &Self, end: &Self, times: u64) -> Option<Self> {
+                end.checked_sub(*start)?
+                    .checked_mul(<$t>::try_from(times).ok()?)?
+                    .checked_add(*self)
+            }
+        })*
+    };
+}
+
+impl_additive!(u32, u64, u128, usize, i32, i64, i128);
+
+impl<T: Additive> Additive for Vec<T> {
+    fn add_cycles(&self, start: &Self, end: &Self, times: u64) -> Option<Self> {
+        self.iter()
+            .zip(start)
+            .zip(end)
+            .map(|((value, start), end)| value.add_cycles(start, end, times))
+            .collect()
+    }
+}
+
+/// Repetition in a sequence of states: the state after `start + length` steps has the same key as after `start` steps
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// Number of steps before `step` that lead to the same key, and how many full cycles are skipped that way
+    fn reduce(&self, step: u64) -> (usize, u64) {
+        if step < self.start as u64 {
+            return (step as usize, 0);
+        }
+        let offset = step - self.start as u64;
+        let length = self.length as u64;
+        (self.start + (offset % length) as usize, offset / length)
+    }
+}
+
+/// Detected cycle, together with the metric observed up to the end of the first cycle
+#[derive(Debug, Clone)]
+pub struct Run<M> {
+    pub cycle: Cycle,
+    /// Value of the metric after each step, starting with the initial state
+    history: Vec<M>,
+}
+
+impl<M: Additive> Run<M> {
+    /// Value of the metric after any number of steps, or `None` if it overflows
+    ///
+    /// It assumes that the metric increases by the same amount during every cycle.
+    pub fn metric_at(&self, step: u64) -> Option<M> {
+        let (reduced, cycles) = self.cycle.reduce(step);
+        let Cycle { start, length } = self.cycle;
+        if cycles == 0 {
+            return Some(self.history[reduced].clone());
+        }
+        self.history[reduced].add_cycles(
+            &self.history[start],
+            &self.history[start + length],
+            cycles,
+        )
+    }
+}
+
+/// Applies `step` until a key is seen twice, remembering the step at which each key was seen
+///
+/// The sequence of keys must eventually repeat, and two states with the same key must have the same future.
+pub fn detect_hashing<S, K, M>(
+    mut state: S,
+    mut step: impl FnMut(&mut S),
+    mut key: impl FnMut(&S) -> K,
+    mut metric: impl FnMut(&S) -> M,
+) -> Run<M>
+where
+    K: Eq + Hash,
+{
+    let mut seen = HashMap::new();
+    let mut history = Vec::new();
+    loop {
+        history.push(metric(&state));
+        let index = history.len() - 1;
+        if let Some(start) = seen.insert(key(&state), index) {
+            return Run {
+                cycle: Cycle {
+                    start,
+                    length: index - start,
+                },
+                history,
+            };
+        }
+        step(&mut state);
+    }
+}
+
+/// Same as [`detect_hashing`], but using Brent's algorithm, which only keeps two keys in memory at once
+///
+/// The states are stepped through about three times, and the metric is only computed during the last pass.
+pub fn detect_brent<S, K, M>(
+    initial: S,
+    mut step: impl FnMut(&mut S),
+    mut key: impl FnMut(&S) -> K,
+    mut metric: impl FnMut(&S) -> M,
+) -> Run<M>
+where
+    S: Clone,
+    K: Eq,
+{
+    // Find the cycle length by moving the tortoise to the hare at every power of two
+    let mut hare = initial.clone();
+    step(&mut hare);
+    let mut tortoise = key(&initial);
+    let (mut power, mut length) = (1, 1);
+    while key(&hare) != tortoise {
+        if power == length {
+            tortoise = key(&hare);
+            power *= 2;
+            length = 0;
+        }
+        step(&mut hare);
+        length += 1;
+    }
+
+    // Find the cycle start by moving two states `length` steps apart until they meet
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    (0..length).for_each(|_| step(&mut hare));
+    let mut start = 0;
+    while key(&tortoise) != key(&hare) {
+        step(&mut tortoise);
+        step(&mut hare);
+        start += 1;
+    }
+
+    let mut state = initial;
+    let mut history = Vec::with_capacity(start + length + 1);
+    history.push(metric(&state));
+    for _ in 0..start + length {
+        step(&mut state);
+        history.push(metric(&state));
+    }
+    Run {
+        cycle: Cycle { start, length },
+        history,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `x -> x² + 1 mod n`, counting the steps
+    fn square_plus_one(n: u64) -> impl FnMut(&mut (u64, u64)) {
+        move |(x, steps)| {
+            *x = (*x * *x + 1) % n;
+            *steps += 1;
+        }
+    }
+
+    #[rstest]
+    #[case(255, 2, Cycle { start: 0, length: 6 })]
+    #[case(1000, 3, Cycle { start: 5, length: 6 })]
+    #[case(7919, 1, Cycle { start: 192, length: 57 })]
+    fn both_modes_should_find_same_cycle(
+        #[case] n: u64,
+        #[case] initial: u64,
+        #[case] expected: Cycle,
+    ) {
+        let hashing = detect_hashing((initial, 0), square_plus_one(n), |s| s.0, |s| s.1);
+        let brent = detect_brent((initial, 0), square_plus_one(n), |s| s.0, |s| s.1);
+        assert_eq!(hashing.cycle, expected);
+        assert_eq!(brent.cycle, expected);
+        assert_eq!(hashing.history, brent.history);
+    }
+
+    /// Height growing by 3 every 4 steps, after a prefix of 2 steps
+    fn height(step: u64) -> u64 {
+        match step {
+            0 => 0,
+            1 => 5,
+            _ => [6, 6, 7, 9][(step as usize - 2) % 4] + 3 * ((step - 2) / 4),
+        }
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(5)]
+    #[case(6)]
+    #[case(7)]
+    #[case(10)]
+    #[case(1_000_000_000_003)]
+    fn should_extrapolate_metric(#[case] step: u64) {
+        let run = detect_hashing(
+            0,
+            |i| *i += 1,
+            |&i| if i < 2 { i } else { 2 + (i - 2) % 4 },
+            |&i| height(i),
+        );
+        assert_eq!(
+            run.cycle,
+            Cycle {
+                start: 2,
+                length: 4
+            }
+        );
+        assert_eq!(run.metric_at(step), Some(height(step)));
+    }
+
+    #[rstest]
+    #[case(1_000_000_000, Some(3_000_000_000))]
+    #[case(2_000_000_000, None)]
+    #[case(u64::MAX, None)]
+    fn should_detect_metric_overflow(#[case] step: u64, #[case] expected: Option<u32>) {
+        let run = detect_hashing(0_u32, |i| *i += 3, |_| (), |&i| i);
+        assert_eq!(run.metric_at(step), expected);
+    }
+
+    #[test]
+    fn should_extrapolate_every_component_of_vectors() {
+        let run = detect_brent(
+            (0_u8, vec![0_u64, 0]),
+            |(i, counts)| {
+                counts[*i as usize] += 1;
+                *i = 1 - *i;
+            },
+            |(i, _)| *i,
+            |(_, counts)| counts.clone(),
+        );
+        assert_eq!(
+            run.cycle,
+            Cycle {
+                start: 0,
+                length: 2
+            }
+        );
+        assert_eq!(run.metric_at(7), Some(vec![4, 3]));
+    }
+}
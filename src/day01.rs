@@ -1,21 +1,104 @@
+use std::{cmp::Reverse, collections::BTreeMap, str::FromStr};
+
+use crate::ranking;
+
 type Output = u64;
 
 pub fn part_1(input: &str) -> Output {
-    parse(input).max().unwrap_or_default()
+    let inventory: Inventory = input.parse().unwrap();
+    inventory.top_n(1).into_iter().map(|(_, c)| c).sum()
 }
 
 pub fn part_2(input: &str) -> Output {
-    let mut elves: Vec<_> = parse(input).collect();
-    elves.sort();
-    elves.into_iter().rev().take(3).sum()
+    let inventory: Inventory = input.parse().unwrap();
+    inventory.top_n(3).into_iter().map(|(_, c)| c).sum()
 }
 
-fn parse(input: &str) -> impl Iterator<Item = Output> + '_ {
-    input.split("\n\n").map(|elf| {
-        elf.lines()
-            .filter_map(|fruit| fruit.parse::<Output>().ok())
-            .sum()
-    })
+/// Calories of the items carried by each elf
+pub struct Inventory {
+    elves: Vec<Vec<Output>>,
+}
+
+impl Inventory {
+    /// Items carried by each elf, together with the index of the elf
+    pub fn elves(&self) -> impl Iterator<Item = (usize, &[Output])> + '_ {
+        self.elves.iter().map(Vec::as_slice).enumerate()
+    }
+
+    /// Total calories carried by each elf, together with the index of the elf
+    pub fn totals(&self) -> impl Iterator<Item = (usize, Output)> + '_ {
+        self.elves().map(|(i, items)| (i, items.iter().sum()))
+    }
+
+    /// The `n` elves carrying the most calories, sorted by decreasing total
+    ///
+    /// Ties are broken by elf index.
+    pub fn top_n(&self, n: usize) -> Vec<(usize, Output)> {
+        let totals = self.totals().map(|(i, total)| (total, Reverse(i)));
+        ranking::top_k(totals, n)
+            .into_iter()
+            .map(|(total, Reverse(i))| (i, total))
+            .collect()
+    }
+
+    /// Statistics about the total calories carried by each elf
+    ///
+    /// The histogram counts the elves per bucket of `bucket_size` calories, keyed by the lower bound of the bucket.
+    pub fn statistics(&self, bucket_size: Output) -> Option<Statistics> {
+        let mut totals: Vec<_> = self.totals().map(|(_, t)| t).collect();
+        if totals.is_empty() {
+            return None;
+        }
+        totals.sort_unstable();
+        let len = totals.len();
+        let median = if len % 2 == 0 {
+            (totals[len / 2 - 1] + totals[len / 2]) as f64 / 2.0
+        } else {
+            totals[len / 2] as f64
+        };
+        let mut histogram = BTreeMap::new();
+        for total in &totals {
+            *histogram
+                .entry(total - total % bucket_size.max(1))
+                .or_insert(0) += 1;
+        }
+        Some(Statistics {
+            mean: totals.iter().sum::<Output>() as f64 / len as f64,
+            median,
+            histogram,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    pub mean: f64,
+    pub median: f64,
+    pub histogram: BTreeMap<Output, usize>,
+}
+
+impl FromStr for Inventory {
+    type Err = String;
+
+    /// Elves are separated by blank lines
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut elves = vec![Vec::new()];
+        for (n, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                if elves.last().map_or(false, |items| !items.is_empty()) {
+                    elves.push(Vec::new());
+                }
+                continue;
+            }
+            let calories = line
+                .parse()
+                .map_err(|e| format!("invalid calories on line {}: {line:?} ({e})", n + 1))?;
+            elves.last_mut().unwrap().push(calories);
+        }
+        elves.retain(|items| !items.is_empty());
+        Ok(Self { elves })
+    }
 }
 
 #[cfg(test)]
@@ -59,4 +142,53 @@ mod tests {
     fn test_part_2(#[case] input: &str, #[case] expected: Output) {
         assert_eq!(part_2(input.trim()), expected);
     }
+
+    #[rstest]
+    #[case(0, &[])]
+    #[case(1, &[(3, 24000)])]
+    #[case(3, &[(3, 24000), (2, 11000), (4, 10000)])]
+    #[case(9, &[(3, 24000), (2, 11000), (4, 10000), (0, 6000), (1, 4000)])]
+    fn should_find_top_n(#[case] n: usize, #[case] expected: &[(usize, Output)]) {
+        let inventory: Inventory = EXAMPLE.trim().parse().unwrap();
+        assert_eq!(inventory.top_n(n), expected);
+    }
+
+    #[test]
+    fn top_n_should_break_ties_by_index() {
+        let inventory: Inventory = "1\n\n2\n\n2\n\n1".parse().unwrap();
+        assert_eq!(inventory.top_n(3), vec![(1, 2), (2, 2), (0, 1)]);
+    }
+
+    #[test]
+    fn should_keep_items_of_each_elf() {
+        let inventory: Inventory = EXAMPLE.trim().parse().unwrap();
+        let elves: Vec<_> = inventory.elves().collect();
+        assert_eq!(elves[0], (0, &[1000, 2000, 3000][..]));
+        assert_eq!(elves[4], (4, &[10000][..]));
+        assert_eq!(elves.len(), 5);
+    }
+
+    #[test]
+    fn should_compute_statistics() {
+        let inventory: Inventory = EXAMPLE.trim().parse().unwrap();
+        let statistics = inventory.statistics(5000).unwrap();
+        assert_eq!(statistics.mean, 11000.0);
+        assert_eq!(statistics.median, 10000.0);
+        assert_eq!(
+            statistics.histogram.into_iter().collect::<Vec<_>>(),
+            vec![(0, 1), (5000, 1), (10000, 2), (20000, 1)]
+        );
+        let even: Inventory = "1\n\n2\n\n4\n\n10".parse().unwrap();
+        assert_eq!(even.statistics(1).unwrap().median, 3.0);
+        assert_eq!("".parse::<Inventory>().unwrap().statistics(1), None);
+    }
+
+    #[rstest]
+    #[case("100\nabc")]
+    #[case("100\n\n-5")]
+    #[case("1.5")]
+    #[case("100 200")]
+    fn should_report_malformed_lines(#[case] input: &str) {
+        assert!(input.parse::<Inventory>().is_err());
+    }
 }
@@ -1,102 +1,386 @@
+use std::collections::HashMap;
+
 type Output = u64;
 
-pub fn part_1(input: &str) -> Output {
-    input.lines().map(part_1_score).sum()
-}
+/// Index of a shape in a [`Game`]
+pub type Shape = usize;
 
-fn part_1_score(input: &str) -> Output {
-    let (opponent, me) = input.split_once(' ').unwrap();
-    let opponent = Shape::from_str(opponent).unwrap();
-    let me = match me {
-        "X" => Shape::Rock,
-        "Y" => Shape::Paper,
-        "Z" => Shape::Scissors,
-        _ => panic!(),
-    };
-    me.play_against(opponent).score() + me.score()
+pub fn part_1(input: &str) -> Output {
+    Game::classic()
+        .score(input, &Strategy::classic_shapes())
+        .unwrap()
 }
 
 pub fn part_2(input: &str) -> Output {
-    input.lines().map(part_2_score).sum()
+    Game::classic()
+        .score(input, &Strategy::classic_outcomes())
+        .unwrap()
 }
 
-fn part_2_score(input: &str) -> Output {
-    let (opponent, result) = input.split_once(' ').unwrap();
-    let opponent = Shape::from_str(opponent).unwrap();
-    let result = Result::from_str(result).unwrap();
-    let me = what_to_play(opponent, result);
-    result.score() + me.score()
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Outcome {
+    Win,
+    Loose,
+    Draw,
 }
 
+/// Score granted for each outcome of a round
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Shape {
-    Rock,
-    Paper,
-    Scissors,
-}
-
-impl Shape {
-    fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "A" => Some(Shape::Rock),
-            "B" => Some(Shape::Paper),
-            "C" => Some(Shape::Scissors),
-            _ => None,
+pub struct OutcomeScores {
+    pub win: Output,
+    pub draw: Output,
+    pub loose: Output,
+}
+
+impl Default for OutcomeScores {
+    fn default() -> Self {
+        Self {
+            win: 6,
+            draw: 3,
+            loose: 0,
+        }
+    }
+}
+
+impl OutcomeScores {
+    fn of(self, outcome: Outcome) -> Output {
+        match outcome {
+            Outcome::Win => self.win,
+            Outcome::Draw => self.draw,
+            Outcome::Loose => self.loose,
+        }
+    }
+}
+
+/// Rules of a generalised rock-paper-scissors game
+#[derive(Debug, Clone)]
+pub struct Game {
+    names: Vec<String>,
+    scores: Vec<Output>,
+    /// `beats[a][b]` is true if the shape `a` beats the shape `b`
+    beats: Vec<Vec<bool>>,
+    outcome_scores: OutcomeScores,
+}
+
+impl Game {
+    /// Rock, paper and scissors, scoring respectively 1, 2 and 3
+    pub fn classic() -> Self {
+        Self::cyclic(&["Rock", "Paper", "Scissors"], OutcomeScores::default()).unwrap()
+    }
+
+    /// Game where each shape beats the `(n - 1) / 2` shapes preceding it (wrapping around)
+    ///
+    /// Each shape scores its position in the list, starting at 1.
+    pub fn cyclic(names: &[&str], outcome_scores: OutcomeScores) -> Result<Self, String> {
+        let n = names.len();
+        let beats = (0..n)
+            .map(|a| (0..n).map(|b| a != b && (a + n - b) % n <= n / 2).collect())
+            .collect();
+        let shapes = names
+            .iter()
+            .zip(1..)
+            .map(|(name, score)| (name.to_string(), score))
+            .collect();
+        Self::new(shapes, beats, outcome_scores)
+    }
+
+    /// Game with the given `(name, score)` shapes, where `beats[a][b]` is true if the shape `a` beats the shape `b`
+    ///
+    /// There must be an odd number of shapes, at least 3 so that every outcome is possible,
+    /// and each of them must beat exactly half of the other ones.
+    pub fn new(
+        shapes: Vec<(String, Output)>,
+        beats: Vec<Vec<bool>>,
+        outcome_scores: OutcomeScores,
+    ) -> Result<Self, String> {
+        let n = shapes.len();
+        if n % 2 == 0 || n < 3 {
+            return Err(format!(
+                "expected an odd number of shapes, at least 3, got {n}"
+            ));
+        }
+        if beats.len() != n || beats.iter().any(|row| row.len() != n) {
+            return Err(format!("the beats relation must be a {n}x{n} table"));
+        }
+        for a in 0..n {
+            if beats[a][a] {
+                return Err(format!("{} cannot beat itself", shapes[a].0));
+            }
+            for b in (a + 1)..n {
+                if beats[a][b] == beats[b][a] {
+                    return Err(format!(
+                        "exactly one of {} and {} must beat the other",
+                        shapes[a].0, shapes[b].0
+                    ));
+                }
+            }
+            let beaten = beats[a].iter().filter(|b| **b).count();
+            if beaten != n / 2 {
+                return Err(format!(
+                    "{} beats {beaten} shapes instead of {}",
+                    shapes[a].0,
+                    n / 2
+                ));
+            }
+        }
+        let (names, scores) = shapes.into_iter().unzip();
+        Ok(Self {
+            names,
+            scores,
+            beats,
+            outcome_scores,
+        })
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = Shape> {
+        0..self.names.len()
+    }
+
+    /// Finds a shape by its name
+    pub fn shape(&self, name: &str) -> Option<Shape> {
+        self.names.iter().position(|n| n == name)
+    }
+
+    pub fn name(&self, shape: Shape) -> &str {
+        &self.names[shape]
+    }
+
+    /// Outcome for the player playing `me`
+    pub fn play(&self, me: Shape, opponent: Shape) -> Outcome {
+        if me == opponent {
+            Outcome::Draw
+        } else if self.beats[me][opponent] {
+            Outcome::Win
+        } else {
+            Outcome::Loose
         }
     }
 
-    fn play_against(self, other: Self) -> Result {
-        if self == other {
-            return Result::Draw;
+    /// Best scoring shape to play against `opponent` in order to get the `wanted` outcome
+    pub fn what_to_play(&self, opponent: Shape, wanted: Outcome) -> Shape {
+        self.shapes()
+            .filter(|me| self.play(*me, opponent) == wanted)
+            .max_by_key(|me| self.scores[*me])
+            .unwrap()
+    }
+
+    /// Score of the player playing `me`
+    pub fn round_score(&self, me: Shape, opponent: Shape) -> Output {
+        self.scores[me] + self.outcome_scores.of(self.play(me, opponent))
+    }
+
+    /// Shape to play against `opponent` according to the `response`
+    fn resolve(&self, opponent: Shape, response: Response) -> Shape {
+        match response {
+            Response::Shape(me) => me,
+            Response::Outcome(outcome) => self.what_to_play(opponent, outcome),
+        }
+    }
+
+    /// Compares the strategy `guide` to the best response that could be played in every round
+    ///
+    /// Fails if the guide is empty, or if the strategy refers to a shape that isn't part of the game.
+    pub fn analyze(&self, guide: &str, strategy: &Strategy) -> Result<Report, String> {
+        self.check_shapes(strategy)?;
+        let guide_score = self.score(guide, strategy)?;
+        let frequencies = self.opponent_frequencies(guide, strategy)?;
+        if frequencies.iter().all(|f| *f == 0) {
+            return Err("the guide is empty".to_string());
         }
-        match (self, other) {
-            (Shape::Rock, Shape::Scissors)
-            | (Shape::Scissors, Shape::Paper)
-            | (Shape::Paper, Shape::Rock) => Result::Win,
-            _ => Result::Loose,
+        let mut responses: Vec<_> = strategy.response.iter().collect();
+        responses.sort_by_key(|(letter, _)| *letter);
+        let (best_letter, best_response, best_score) = responses
+            .into_iter()
+            .map(|(letter, response)| {
+                let score = frequencies
+                    .iter()
+                    .enumerate()
+                    .map(|(opponent, count)| {
+                        count * self.round_score(self.resolve(opponent, *response), opponent)
+                    })
+                    .sum::<Output>();
+                (letter.clone(), *response, score)
+            })
+            .rev()
+            .max_by_key(|(_, _, score)| *score)
+            .ok_or("the strategy has no response")?;
+        Ok(Report {
+            rounds: frequencies.iter().sum(),
+            guide_score,
+            best_letter,
+            best_response,
+            best_score,
+        })
+    }
+
+    /// Makes sure every shape mentioned by the `strategy` is part of the game
+    fn check_shapes(&self, strategy: &Strategy) -> Result<(), String> {
+        let responses = strategy
+            .response
+            .iter()
+            .filter_map(|(letter, response)| match response {
+                Response::Shape(shape) => Some((letter, *shape)),
+                Response::Outcome(_) => None,
+            });
+        match strategy
+            .opponent
+            .iter()
+            .map(|(letter, shape)| (letter, *shape))
+            .chain(responses)
+            .find(|(_, shape)| *shape >= self.names.len())
+        {
+            Some((letter, shape)) => Err(format!("unknown shape {shape} for letter {letter:?}")),
+            None => Ok(()),
         }
     }
 
-    fn score(self) -> Output {
-        match self {
-            Shape::Rock => 1,
-            Shape::Paper => 2,
-            Shape::Scissors => 3,
+    /// Number of times the opponent plays each shape in the `guide`
+    pub fn opponent_frequencies(
+        &self,
+        guide: &str,
+        strategy: &Strategy,
+    ) -> Result<Vec<u64>, String> {
+        let mut frequencies = vec![0; self.names.len()];
+        for line in guide.lines() {
+            let (opponent, _) = strategy.parse_round(line)?;
+            *frequencies
+                .get_mut(opponent)
+                .ok_or_else(|| format!("unknown opponent shape in {line:?}"))? += 1;
         }
+        Ok(frequencies)
+    }
+
+    /// Total score obtained by following the strategy `guide`
+    pub fn score(&self, guide: &str, strategy: &Strategy) -> Result<Output, String> {
+        guide
+            .lines()
+            .map(|line| {
+                let (opponent, me) = strategy.parse_round(line)?;
+                let unknown = |shape: Shape| shape >= self.names.len();
+                if unknown(opponent) {
+                    return Err(format!("unknown opponent shape in {line:?}"));
+                }
+                let me = self.resolve(opponent, me);
+                if unknown(me) {
+                    return Err(format!("unknown response shape in {line:?}"));
+                }
+                Ok(self.round_score(me, opponent))
+            })
+            .sum()
+    }
+}
+
+/// Analysis of both interpretations of a classic strategy guide
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    /// Number of times the opponent plays rock, paper and scissors
+    pub opponent_frequencies: Vec<u64>,
+    /// The second column tells what shape to play
+    pub shapes: Report,
+    /// The second column tells how the round must end
+    pub outcomes: Report,
+}
+
+pub fn analyze(input: &str) -> Result<Analysis, String> {
+    let game = Game::classic();
+    Ok(Analysis {
+        opponent_frequencies: game.opponent_frequencies(input, &Strategy::classic_shapes())?,
+        shapes: game.analyze(input, &Strategy::classic_shapes())?,
+        outcomes: game.analyze(input, &Strategy::classic_outcomes())?,
+    })
+}
+
+/// Comparison of a strategy guide with the best fixed response against the opponent's observed shapes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Number of rounds in the guide, which is never 0
+    pub rounds: u64,
+    /// Total score obtained by following the guide
+    pub guide_score: Output,
+    /// Letter of the response that would score the most if played in every round
+    pub best_letter: String,
+    pub best_response: Response,
+    /// Total score obtained by playing the best response in every round
+    pub best_score: Output,
+}
+
+impl Report {
+    /// Average score per round when following the guide
+    pub fn expected_score(&self) -> f64 {
+        self.guide_score as f64 / self.rounds as f64
+    }
+
+    /// Average score per round when playing the best response
+    pub fn best_expected_score(&self) -> f64 {
+        self.best_score as f64 / self.rounds as f64
+    }
+
+    /// How many points the guide loses (or wins, if negative) compared to the best response
+    pub fn gap(&self) -> i64 {
+        self.best_score as i64 - self.guide_score as i64
     }
 }
 
+/// What the second column of the guide tells to do
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Result {
-    Win,
-    Loose,
-    Draw,
+pub enum Response {
+    Shape(Shape),
+    Outcome(Outcome),
 }
 
-impl Result {
-    fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "X" => Some(Result::Loose),
-            "Y" => Some(Result::Draw),
-            "Z" => Some(Result::Win),
-            _ => None,
+/// How to interpret the letters of a strategy guide
+#[derive(Debug, Clone)]
+pub struct Strategy {
+    opponent: HashMap<String, Shape>,
+    response: HashMap<String, Response>,
+}
+
+impl Strategy {
+    pub fn new(opponent: &[(&str, Shape)], response: &[(&str, Response)]) -> Self {
+        Self {
+            opponent: opponent.iter().map(|(l, s)| (l.to_string(), *s)).collect(),
+            response: response.iter().map(|(l, r)| (l.to_string(), *r)).collect(),
         }
     }
 
-    fn score(self) -> Output {
-        match self {
-            Result::Win => 6,
-            Result::Draw => 3,
-            Result::Loose => 0,
-        }
+    /// `A`, `B`, `C` and `X`, `Y`, `Z` are respectively rock, paper and scissors
+    pub fn classic_shapes() -> Self {
+        Self::new(
+            &[("A", 0), ("B", 1), ("C", 2)],
+            &[
+                ("X", Response::Shape(0)),
+                ("Y", Response::Shape(1)),
+                ("Z", Response::Shape(2)),
+            ],
+        )
     }
-}
 
-fn what_to_play(opponent: Shape, wanted_result: Result) -> Shape {
-    [Shape::Rock, Shape::Paper, Shape::Scissors]
-        .into_iter()
-        .find(|shape| shape.play_against(opponent) == wanted_result)
-        .unwrap()
+    /// `A`, `B`, `C` are respectively rock, paper and scissors, and `X`, `Y`, `Z` tell to loose, draw or win
+    pub fn classic_outcomes() -> Self {
+        Self::new(
+            &[("A", 0), ("B", 1), ("C", 2)],
+            &[
+                ("X", Response::Outcome(Outcome::Loose)),
+                ("Y", Response::Outcome(Outcome::Draw)),
+                ("Z", Response::Outcome(Outcome::Win)),
+            ],
+        )
+    }
+
+    fn parse_round(&self, line: &str) -> Result<(Shape, Response), String> {
+        let (opponent, me) = line
+            .split_once(' ')
+            .ok_or_else(|| format!("invalid round: {line:?}"))?;
+        let opponent = self
+            .opponent
+            .get(opponent)
+            .ok_or_else(|| format!("unknown opponent letter: {opponent:?}"))?;
+        let me = self
+            .response
+            .get(me)
+            .ok_or_else(|| format!("unknown response letter: {me:?}"))?;
+        Ok((*opponent, *me))
+    }
 }
 
 #[cfg(test)]
@@ -111,6 +395,10 @@ C Z
 
     const INPUT: &str = include_str!("day02/input.txt");
 
+    const ROCK: Shape = 0;
+    const PAPER: Shape = 1;
+    const SCISSORS: Shape = 2;
+
     #[rstest]
     #[case("A Y", 8)]
     #[case("B X", 1)]
@@ -132,35 +420,191 @@ C Z
     }
 
     #[rstest]
-    #[case(Shape::Rock, Shape::Scissors)]
-    #[case(Shape::Paper, Shape::Rock)]
-    #[case(Shape::Scissors, Shape::Paper)]
+    #[case(ROCK, SCISSORS)]
+    #[case(PAPER, ROCK)]
+    #[case(SCISSORS, PAPER)]
     fn wins(#[case] a: Shape, #[case] b: Shape) {
-        assert_eq!(a.play_against(b), Result::Win);
-        assert_eq!(b.play_against(a), Result::Loose);
-        assert_eq!(what_to_play(b, Result::Win), a);
-        assert_eq!(what_to_play(a, Result::Loose), b);
+        let game = Game::classic();
+        assert_eq!(game.play(a, b), Outcome::Win);
+        assert_eq!(game.play(b, a), Outcome::Loose);
+        assert_eq!(game.what_to_play(b, Outcome::Win), a);
+        assert_eq!(game.what_to_play(a, Outcome::Loose), b);
     }
 
     #[rstest]
-    fn draws(#[values(Shape::Rock, Shape::Paper, Shape::Scissors)] shape: Shape) {
-        assert_eq!(shape.play_against(shape), Result::Draw);
-        assert_eq!(what_to_play(shape, Result::Draw), shape);
+    fn draws(#[values(ROCK, PAPER, SCISSORS)] shape: Shape) {
+        let game = Game::classic();
+        assert_eq!(game.play(shape, shape), Outcome::Draw);
+        assert_eq!(game.what_to_play(shape, Outcome::Draw), shape);
     }
 
     #[rstest]
-    #[case(Shape::Rock, 1)]
-    #[case(Shape::Paper, 2)]
-    #[case(Shape::Scissors, 3)]
+    #[case(ROCK, 1)]
+    #[case(PAPER, 2)]
+    #[case(SCISSORS, 3)]
     fn shape_score(#[case] shape: Shape, #[case] score: Output) {
-        assert_eq!(shape.score(), score);
+        let game = Game::classic();
+        assert_eq!(game.round_score(shape, shape), score + 3);
     }
 
     #[rstest]
-    #[case(Result::Loose, 0)]
-    #[case(Result::Draw, 3)]
-    #[case(Result::Win, 6)]
-    fn result_score(#[case] result: Result, #[case] score: Output) {
-        assert_eq!(result.score(), score);
+    #[case(Outcome::Loose, 0)]
+    #[case(Outcome::Draw, 3)]
+    #[case(Outcome::Win, 6)]
+    fn result_score(#[case] result: Outcome, #[case] score: Output) {
+        assert_eq!(OutcomeScores::default().of(result), score);
+    }
+
+    fn lizard_spock() -> Game {
+        Game::cyclic(
+            &["Rock", "Spock", "Paper", "Lizard", "Scissors"],
+            OutcomeScores::default(),
+        )
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case("Scissors", "Paper")]
+    #[case("Paper", "Rock")]
+    #[case("Rock", "Lizard")]
+    #[case("Lizard", "Spock")]
+    #[case("Spock", "Scissors")]
+    #[case("Scissors", "Lizard")]
+    #[case("Lizard", "Paper")]
+    #[case("Paper", "Spock")]
+    #[case("Spock", "Rock")]
+    #[case("Rock", "Scissors")]
+    fn lizard_spock_wins(#[case] a: &str, #[case] b: &str) {
+        let game = lizard_spock();
+        let (a, b) = (game.shape(a).unwrap(), game.shape(b).unwrap());
+        assert_eq!(game.play(a, b), Outcome::Win);
+        assert_eq!(game.play(b, a), Outcome::Loose);
+    }
+
+    #[test]
+    fn should_score_with_custom_letters_and_scores() {
+        let game = lizard_spock();
+        let spock = game.shape("Spock").unwrap();
+        let lizard = game.shape("Lizard").unwrap();
+        let strategy = Strategy::new(
+            &[("s", spock), ("l", lizard)],
+            &[
+                ("1", Response::Shape(spock)),
+                ("2", Response::Shape(lizard)),
+                ("+", Response::Outcome(Outcome::Win)),
+            ],
+        );
+        assert_eq!(game.score("s 1", &strategy), Ok(2 + 3));
+        assert_eq!(game.score("s 2", &strategy), Ok(4 + 6));
+        // Both paper and lizard beat spock, the best scoring one is picked
+        assert_eq!(game.score("s +", &strategy), Ok(4 + 6));
+        assert!(game.score("x 1", &strategy).is_err());
+        assert!(game.score("s 3", &strategy).is_err());
+        let out_of_bounds = Strategy::new(&[("a", 7)], &[("+", Response::Outcome(Outcome::Win))]);
+        assert!(game.score("a +", &out_of_bounds).is_err());
+
+        let outcome_scores = OutcomeScores {
+            win: 10,
+            draw: 1,
+            loose: 0,
+        };
+        let shapes = vec![
+            ("R".to_string(), 5),
+            ("P".to_string(), 0),
+            ("S".to_string(), 2),
+        ];
+        let beats = vec![
+            vec![false, false, true],
+            vec![true, false, false],
+            vec![false, true, false],
+        ];
+        let game = Game::new(shapes, beats, outcome_scores).unwrap();
+        assert_eq!(
+            game.score("A Y\nB X\nC Z", &Strategy::classic_shapes()),
+            Ok(10 + 5 + 3)
+        );
+    }
+
+    #[test]
+    fn should_analyze_example() {
+        let analysis = analyze(EXAMPLE.trim()).unwrap();
+        assert_eq!(analysis.opponent_frequencies, vec![1, 1, 1]);
+        assert_eq!(
+            analysis.shapes,
+            Report {
+                rounds: 3,
+                guide_score: 15,
+                best_letter: "Z".to_string(),
+                best_response: Response::Shape(SCISSORS),
+                best_score: 18,
+            }
+        );
+        assert_eq!(analysis.shapes.gap(), 3);
+        assert_eq!(analysis.shapes.expected_score(), 5.0);
+        assert_eq!(analysis.shapes.best_expected_score(), 6.0);
+        assert_eq!(
+            analysis.outcomes,
+            Report {
+                rounds: 3,
+                guide_score: 12,
+                best_letter: "Z".to_string(),
+                best_response: Response::Outcome(Outcome::Win),
+                best_score: 24,
+            }
+        );
+        assert_eq!(analysis.outcomes.gap(), 12);
+    }
+
+    #[rstest]
+    #[case("A X\nA X\nA X", "Y", 24, 24)]
+    #[case("A Y\nA Y\nB Y", "Y", 21, 25)]
+    #[case("A Z\nB Z\nB X", "Z", 21, 26)]
+    fn should_find_best_fixed_response(
+        #[case] guide: &str,
+        #[case] best_letter: &str,
+        #[case] best_score: Output,
+        #[case] best_score_by_outcome: Output,
+    ) {
+        let analysis = analyze(guide).unwrap();
+        assert_eq!(analysis.shapes.best_letter, best_letter);
+        assert_eq!(analysis.shapes.best_score, best_score);
+        assert_eq!(analysis.outcomes.best_letter, "Z");
+        assert_eq!(analysis.outcomes.best_score, best_score_by_outcome);
+    }
+
+    #[test]
+    fn should_reject_invalid_analysis() {
+        assert!(analyze("").is_err());
+        let game = Game::classic();
+        let strategy = Strategy::new(
+            &[("A", ROCK)],
+            &[("X", Response::Shape(ROCK)), ("Y", Response::Shape(7))],
+        );
+        assert!(game.analyze("A X", &strategy).is_err());
+    }
+
+    #[rstest]
+    #[case::even(&["a", "b"][..])]
+    #[case::empty(&[][..])]
+    #[case::single(&["a"][..])]
+    fn cyclic_game_should_require_odd_shape_count(#[case] names: &[&str]) {
+        assert!(Game::cyclic(names, OutcomeScores::default()).is_err());
+    }
+
+    #[rstest]
+    #[case::self_beat(vec![vec![true, false, true], vec![true, false, false], vec![false, true, false]])]
+    #[case::both_win(vec![vec![false, true, true], vec![true, false, false], vec![false, true, false]])]
+    #[case::unbalanced(vec![vec![false, true, true], vec![false, false, true], vec![false, false, false]])]
+    #[case::wrong_size(vec![vec![false, true], vec![false, false]])]
+    fn should_reject_invalid_beats_relation(#[case] beats: Vec<Vec<bool>>) {
+        let shapes = vec![("a".into(), 1), ("b".into(), 2), ("c".into(), 3)];
+        assert!(Game::new(shapes, beats, OutcomeScores::default()).is_err());
+    }
+
+    #[test]
+    fn should_reject_single_shape_game() {
+        let shapes = vec![("a".into(), 1)];
+        let beats = vec![vec![false]];
+        assert!(Game::new(shapes, beats, OutcomeScores::default()).is_err());
     }
 }
@@ -1,3 +1,5 @@
+use std::{fmt::Debug, str::FromStr};
+
 use itertools::Itertools;
 
 type Output = u64;
@@ -7,39 +9,107 @@ pub fn part_1(input: &str) -> Output {
         .lines()
         .filter_map(|line| {
             let (comp1, comp2) = line.split_at(line.len() / 2);
-            find_item_in_all(&[comp1, comp2])
+            find_item_in_all(&[comp1, comp2]).ok()
         })
         .map(score_of)
         .sum()
 }
 
 pub fn part_2(input: &str) -> Output {
+    sum_of_badges(input, 3).unwrap()
+}
+
+/// Sum of the priorities of the item common to each group of `group_size` rucksacks
+pub fn sum_of_badges(input: &str, group_size: usize) -> Result<Output, String> {
+    if group_size == 0 {
+        return Err("groups must contain at least one rucksack".to_string());
+    }
     let mut sum = 0;
-    for group in &input.lines().chunks(3) {
+    for group in &input.lines().chunks(group_size) {
         let group: Vec<_> = group.collect();
-        let Some(badge) = find_item_in_all(&group) else { continue };
-        sum += score_of(badge);
+        if group.len() != group_size {
+            return Err(format!(
+                "last group has {} rucksacks instead of {group_size}",
+                group.len()
+            ));
+        }
+        sum += score_of(find_item_in_all(&group)?);
     }
-    sum
+    Ok(sum)
 }
 
-fn find_item_in_all(sacks: &[&str]) -> Option<char> {
-    sacks
-        .first()?
-        .chars()
-        .find(|&c| sacks.iter().skip(1).all(|s| s.contains(c)))
+/// Returns the only item present in every sack
+fn find_item_in_all(sacks: &[&str]) -> Result<char, String> {
+    let common = sacks
+        .iter()
+        .map(|s| s.parse::<Items>())
+        .fold_ok(Items(!0), Items::intersection)?;
+    common.single().ok_or_else(|| match common.len() {
+        0 => format!("no item is common to {sacks:?}"),
+        _ => format!("several items are common to {sacks:?}: {common:?}"),
+    })
 }
 
 fn score_of(item: char) -> Output {
-    if ('a'..='z').contains(&item) {
+    if item.is_ascii_lowercase() {
         item as Output - 'a' as Output + 1
-    } else if ('A'..='Z').contains(&item) {
+    } else if item.is_ascii_uppercase() {
         item as Output - 'A' as Output + 27
     } else {
         0
     }
 }
 
+fn item_of(priority: u32) -> char {
+    match priority {
+        1..=26 => (b'a' + priority as u8 - 1) as char,
+        27..=52 => (b'A' + priority as u8 - 27) as char,
+        _ => panic!("invalid priority: {priority}"),
+    }
+}
+
+/// Set of items, where the bit at index `n` is set if the item of priority `n` is present
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct Items(u64);
+
+impl Items {
+    fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    fn len(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    fn single(self) -> Option<char> {
+        (self.len() == 1).then(|| item_of(self.0.trailing_zeros()))
+    }
+
+    fn iter(self) -> impl Iterator<Item = char> {
+        (1..=52)
+            .filter(move |p| self.0 & (1 << p) != 0)
+            .map(item_of)
+    }
+}
+
+impl Debug for Items {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromStr for Items {
+    type Err = String;
+
+    fn from_str(sack: &str) -> Result<Self, Self::Err> {
+        sack.chars()
+            .try_fold(Items(0), |Items(mask), item| match score_of(item) {
+                0 => Err(format!("invalid item: {item:?}")),
+                priority => Ok(Items(mask | 1 << priority)),
+            })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +141,7 @@ CrZsJsPPZsGzwwsLwLmpwMDw
     #[case("", 0)]
     #[case("vJrwpWtwJgWrhcsFMMfFFhFp", 16)]
     #[case("jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL", 38)]
+    #[case::malformed_lines_are_skipped("abcd\nvJrwpWtwJgWrhcsFMMfFFhFp\nab12", 16)]
     #[case::example(EXAMPLE, 157)]
     #[case::input(INPUT, 7742)]
     fn test_part_1(#[case] input: &str, #[case] expected: Output) {
@@ -105,6 +176,48 @@ CrZsJsPPZsGzwwsLwLmpwMDw
     #[case(&["ab", "ac", "ad"], 'a')]
     #[case(&["abc", "acd", "czy"], 'c')]
     fn should_find_badge(#[case] sacks: &[&str], #[case] expected: char) {
-        assert_eq!(find_item_in_all(&sacks), Some(expected));
+        assert_eq!(find_item_in_all(sacks), Ok(expected));
+    }
+
+    #[rstest]
+    #[case(&["ab", "cd"])]
+    #[case(&["ab", "ab"])]
+    #[case(&["abc", "abd", "abe"])]
+    #[case(&["a1", "a"])]
+    fn should_reject_group_without_single_badge(#[case] sacks: &[&str]) {
+        assert!(find_item_in_all(sacks).is_err());
+    }
+
+    #[rstest]
+    #[case("a", 1, 1)]
+    #[case("ab\nbc", 2, 2)]
+    #[case("ab\nbc\nbd\nAZ\nZz\nZ", 3, 54)]
+    #[case("aB\nBc\nBd\nBe", 4, 28)]
+    fn should_sum_badges_of_any_group_size(
+        #[case] input: &str,
+        #[case] group_size: usize,
+        #[case] expected: Output,
+    ) {
+        assert_eq!(sum_of_badges(input, group_size), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("a\nb", 2)]
+    #[case("a\na\na", 2)]
+    #[case("a", 0)]
+    #[case::several_badges(EXAMPLE.trim(), 2)]
+    #[case::no_badge(EXAMPLE.trim(), 6)]
+    fn should_reject_invalid_groups(#[case] input: &str, #[case] group_size: usize) {
+        assert!(sum_of_badges(input, group_size).is_err());
+    }
+
+    #[rstest]
+    #[case(1, 'a')]
+    #[case(26, 'z')]
+    #[case(27, 'A')]
+    #[case(52, 'Z')]
+    fn priority_should_round_trip(#[case] priority: u32, #[case] item: char) {
+        assert_eq!(item_of(priority), item);
+        assert_eq!(score_of(item), priority as Output);
     }
 }
@@ -1,6 +1,9 @@
 use std::ops::RangeInclusive;
 
+use crate::interval::IntervalSet;
+
 type Output = usize;
+type Assignment = IntervalSet<u32>;
 
 pub fn part_1(input: &str) -> Output {
     input
@@ -18,11 +21,33 @@ pub fn part_2(input: &str) -> Output {
         .count()
 }
 
-fn parse_line(input: &str) -> Option<(RangeInclusive<u32>, RangeInclusive<u32>)> {
+/// Sum, for each pair, of the number of sections assigned to both elves
+pub fn total_overlap(input: &str) -> u128 {
+    input
+        .lines()
+        .filter_map(parse_line)
+        .map(|(left, right)| left.intersection(&right).len())
+        .sum()
+}
+
+/// Sections that no elf is assigned to, between the smallest and biggest assigned section
+pub fn uncovered_sections(input: &str) -> IntervalSet<u32> {
+    let covered: IntervalSet<u32> = input
+        .lines()
+        .filter_map(parse_line)
+        .flat_map(|(left, right)| left.into_iter().chain(right))
+        .collect();
+    match covered.bounds() {
+        Some(bounds) => IntervalSet::from(bounds).difference(&covered),
+        None => IntervalSet::new(),
+    }
+}
+
+fn parse_line(input: &str) -> Option<(Assignment, Assignment)> {
     let (left, right) = input.split_once(',')?;
     let left = parse_range(left)?;
     let right = parse_range(right)?;
-    Some((left, right))
+    Some((left.into(), right.into()))
 }
 
 fn parse_range(input: &str) -> Option<RangeInclusive<u32>> {
@@ -30,13 +55,12 @@ fn parse_range(input: &str) -> Option<RangeInclusive<u32>> {
     Some(min.parse::<u32>().ok()?..=max.parse::<u32>().ok()?)
 }
 
-fn is_overlap((left, right): &(RangeInclusive<u32>, RangeInclusive<u32>)) -> bool {
-    left.contains(right.start()) || left.contains(right.end()) || right.contains(left.start())
+fn is_overlap((left, right): &(Assignment, Assignment)) -> bool {
+    !left.intersection(right).is_empty()
 }
 
-fn is_full_overlap((left, right): &(RangeInclusive<u32>, RangeInclusive<u32>)) -> bool {
-    (left.contains(right.start()) && left.contains(right.end()))
-        || (right.contains(left.start()) && right.contains(left.end()))
+fn is_full_overlap((left, right): &(Assignment, Assignment)) -> bool {
+    left.is_superset(right) || right.is_superset(left)
 }
 
 #[cfg(test)]
@@ -72,6 +96,28 @@ mod tests {
         assert_eq!(part_2(input.trim()), expected);
     }
 
+    #[rstest]
+    #[case::example(EXAMPLE, 10)]
+    #[case("1-5,5-9", 1)]
+    #[case("1-5,6-9\n2-2,2-2", 1)]
+    #[case::input(INPUT, 24428)]
+    fn should_compute_total_overlap(#[case] input: &str, #[case] expected: u128) {
+        assert_eq!(total_overlap(input.trim()), expected);
+    }
+
+    #[rstest]
+    #[case::example(EXAMPLE, &[])]
+    #[case("2-3,8-9", &[4..=7])]
+    #[case("2-3,8-9\n5-5,12-14", &[4..=4, 6..=7, 10..=11])]
+    #[case("", &[])]
+    fn should_find_uncovered_sections(
+        #[case] input: &str,
+        #[case] expected: &[RangeInclusive<u32>],
+    ) {
+        let expected: IntervalSet<u32> = expected.iter().cloned().collect();
+        assert_eq!(uncovered_sections(input.trim()), expected);
+    }
+
     #[rstest]
     #[case("1-2", 1..=2)]
     fn should_parse_range(#[case] input: &str, #[case] expected: RangeInclusive<u32>) {
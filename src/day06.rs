@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::io::{self, Read};
 
 type Output = usize;
 
@@ -11,16 +11,108 @@ pub fn part_2(input: &str) -> Output {
 }
 
 fn length_until_signal(n: usize, input: &str) -> Option<usize> {
-    let mut set = HashSet::<char>::with_capacity(n);
-    input
-        .char_indices()
-        .map(|(i, _)| (i, &input[i..(i + n)]))
-        .find(|(_, s)| {
-            set.clear();
-            set.extend(s.chars());
-            set.len() == n
-        })
-        .map(|(index, _)| index + n)
+    markers(n, input.as_bytes()).next()
+}
+
+/// Every position right after `size` different consecutive bytes
+pub fn markers(size: usize, bytes: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    let mut detector = MarkerDetector::new(size);
+    bytes
+        .iter()
+        .enumerate()
+        .filter_map(move |(i, b)| detector.push(*b).then_some(i + 1))
+}
+
+/// Same as [`markers`], but reads the bytes in chunks from the given `reader`
+pub fn read_markers<R: Read>(size: usize, reader: R) -> ReadMarkers<R> {
+    ReadMarkers {
+        reader,
+        detector: MarkerDetector::new(size),
+        buffer: [0; 8192],
+        start: 0,
+        end: 0,
+    }
+}
+
+pub struct ReadMarkers<R> {
+    reader: R,
+    detector: MarkerDetector,
+    buffer: [u8; 8192],
+    start: usize,
+    end: usize,
+}
+
+impl<R: Read> Iterator for ReadMarkers<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.start < self.end {
+                let byte = self.buffer[self.start];
+                self.start += 1;
+                if self.detector.push(byte) {
+                    return Some(Ok(self.detector.position()));
+                }
+            }
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(len) => (self.start, self.end) = (0, len),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Sliding window over the last bytes, counting how many times each byte value occurs in it
+struct MarkerDetector {
+    size: usize,
+    window: [u8; 256],
+    counts: [u16; 256],
+    duplicates: usize,
+    position: usize,
+}
+
+impl MarkerDetector {
+    fn new(size: usize) -> Self {
+        assert!(size > 0, "markers must contain at least one byte");
+        Self {
+            size,
+            window: [0; 256],
+            counts: [0; 256],
+            duplicates: 0,
+            position: 0,
+        }
+    }
+
+    /// Feeds the next byte, and returns true if the last `size` bytes are all different
+    fn push(&mut self, byte: u8) -> bool {
+        self.position += 1;
+        // There are only 256 different bytes
+        if self.size > self.window.len() {
+            return false;
+        }
+        let slot = self.position % self.size;
+        if self.position > self.size {
+            let count = &mut self.counts[self.window[slot] as usize];
+            *count -= 1;
+            if *count == 1 {
+                self.duplicates -= 1;
+            }
+        }
+        self.window[slot] = byte;
+        let count = &mut self.counts[byte as usize];
+        *count += 1;
+        if *count == 2 {
+            self.duplicates += 1;
+        }
+        self.position >= self.size && self.duplicates == 0
+    }
+
+    /// Number of bytes pushed so far
+    fn position(&self) -> usize {
+        self.position
+    }
 }
 
 #[cfg(test)]
@@ -45,4 +137,64 @@ mod tests {
     fn test_part_2(#[case] input: &str, #[case] expected: Output) {
         assert_eq!(part_2(input.trim()), expected);
     }
+
+    #[rstest]
+    #[case(4, "", None)]
+    #[case(4, "abc", None)]
+    #[case(4, "aaaaaaaa", None)]
+    #[case(4, "abcabcabc", None)]
+    #[case(1, "a", Some(1))]
+    #[case(2, "éa", Some(2))]
+    #[case(3, "ééa", Some(5))]
+    #[case(4, "aéé", None)]
+    #[case(300, INPUT, None)]
+    fn should_find_first_marker(
+        #[case] size: usize,
+        #[case] input: &str,
+        #[case] expected: Option<usize>,
+    ) {
+        assert_eq!(length_until_signal(size, input), expected);
+    }
+
+    #[rstest]
+    #[case(4, "abcd", &[4])]
+    #[case(4, "abcde", &[4, 5])]
+    #[case(3, "abcabbcab", &[3, 4, 5, 8, 9])]
+    #[case(2, "aabba", &[3, 5])]
+    fn should_find_every_marker(
+        #[case] size: usize,
+        #[case] input: &str,
+        #[case] expected: &[usize],
+    ) {
+        let actual: Vec<_> = markers(size, input.as_bytes()).collect();
+        assert_eq!(actual, expected);
+    }
+
+    /// Reader returning at most 3 bytes at a time
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[rstest]
+    #[case(4)]
+    #[case(14)]
+    fn reader_should_find_same_markers_as_slices(#[case] size: usize) {
+        let expected: Vec<_> = markers(size, INPUT.as_bytes()).collect();
+        let from_chunks: Vec<_> = read_markers(size, INPUT.as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        let from_trickle: Vec<_> = read_markers(size, Trickle(INPUT.as_bytes()))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert!(!expected.is_empty());
+        assert_eq!(from_chunks, expected);
+        assert_eq!(from_trickle, expected);
+    }
 }
@@ -1,8 +1,14 @@
-use std::{convert::Infallible, iter, str::FromStr};
+use std::{cmp::Reverse, iter, str::FromStr};
 
 use grid::Grid;
 
+use crate::{
+    netpbm::{ColorMap, Image},
+    ranking,
+};
+
 type Output = usize;
+type Height = u16;
 
 pub fn part_1(input: &str) -> Output {
     input.parse::<Forest>().unwrap().count_visible()
@@ -12,38 +18,135 @@ pub fn part_2(input: &str) -> Output {
     input.parse::<Forest>().unwrap().max_score()
 }
 
-struct Forest(Grid<u8>);
+pub struct Forest(Grid<Height>);
 
 impl Forest {
-    fn coords(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
-        let (h, w) = self.0.size();
-        (0..w).flat_map(move |x| (0..h).map(move |y| (x, y)))
+    /// Parses a forest of arbitrary heights, separated by whitespace or commas, one row per line
+    pub fn parse_separated(s: &str) -> Result<Self, String> {
+        Self::from_rows(s.lines().map(|line| {
+            line.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|h| !h.is_empty())
+                .map(|h| h.parse().map_err(|e| format!("invalid height {h:?}: {e}")))
+                .collect()
+        }))
+    }
+
+    fn from_rows(rows: impl Iterator<Item = Result<Vec<Height>, String>>) -> Result<Self, String> {
+        let mut cells = Vec::new();
+        let mut width = None;
+        for (y, row) in rows.enumerate() {
+            let row = row?;
+            match width {
+                None => width = Some(row.len()),
+                Some(w) if w != row.len() => {
+                    return Err(format!("row {y} has {} trees instead of {w}", row.len()))
+                }
+                Some(_) => (),
+            }
+            cells.extend(row);
+        }
+        match width {
+            Some(width) if width > 0 => Ok(Forest(Grid::from_vec(cells, width))),
+            _ => Err("empty forest".to_string()),
+        }
     }
 
-    fn count_visible(&self) -> usize {
-        self.coords().filter(|&c| self.is_visible(c)).count()
+    pub fn count_visible(&self) -> usize {
+        self.visibility().into_iter().filter(|v| *v).count()
     }
 
-    fn max_score(&self) -> usize {
-        self.coords()
-            .map(|p| self.score(p))
+    pub fn max_score(&self) -> usize {
+        self.scores()
+            .map(|(_, score)| score)
             .max()
             .unwrap_or_default()
     }
 
-    fn is_visible(&self, (x, y): (usize, usize)) -> bool {
-        let value = self.0[y][x];
-        (0..y).all(|y| self.0[y][x] < value)
-            || ((y + 1)..self.0.rows()).all(|y| self.0[y][x] < value)
-            || (0..x).all(|x| self.0[y][x] < value)
-            || ((x + 1)..self.0.cols()).all(|x| self.0[y][x] < value)
+    /// The `k` best spots for a tree house, sorted by decreasing score
+    ///
+    /// Ties are broken in reading order.
+    pub fn best_tree_houses(&self, k: usize) -> Vec<TreeHouse> {
+        let scores = self
+            .scores()
+            .map(|((x, y), score)| (score, Reverse((y, x))));
+        ranking::top_k(scores, k)
+            .into_iter()
+            .map(|(score, Reverse((y, x)))| {
+                let position = (x, y);
+                TreeHouse {
+                    position,
+                    score,
+                    views: Views {
+                        up: self.visible_trees(position, (0, -1)),
+                        down: self.visible_trees(position, (0, 1)),
+                        left: self.visible_trees(position, (-1, 0)),
+                        right: self.visible_trees(position, (1, 0)),
+                    },
+                }
+            })
+            .collect()
     }
 
-    fn score(&self, coord: (usize, usize)) -> usize {
-        [(0, 1), (0, -1), (1, 0), (-1, 0)]
+    /// Image with one pixel per tree, coloured after its height
+    pub fn image<C: ColorMap<Height>>(&self, colors: &C) -> Image<C::Pixel> {
+        let (h, w) = self.0.size();
+        Image::from_fn(w, h, |(x, y)| colors.color(self.0[y][x]))
+    }
+
+    /// Renders visible trees as `#` and hidden trees as `.`
+    ///
+    /// If a `tree_house` is given, it is marked with `H`, and the trees it can see with `|` or `-`.
+    pub fn render(&self, tree_house: Option<&TreeHouse>) -> String {
+        let w = self.0.cols();
+        let mut cells: Vec<_> = self
+            .visibility()
             .into_iter()
-            .map(|step| self.visible_trees(coord, step))
-            .product()
+            .map(|v| if v { '#' } else { '.' })
+            .collect();
+        if let Some(TreeHouse {
+            position: (x, y),
+            views,
+            ..
+        }) = tree_house
+        {
+            let (x, y) = (*x, *y);
+            for i in 1..=views.up {
+                cells[(y - i) * w + x] = '|';
+            }
+            for i in 1..=views.down {
+                cells[(y + i) * w + x] = '|';
+            }
+            for i in 1..=views.left {
+                cells[y * w + x - i] = '-';
+            }
+            for i in 1..=views.right {
+                cells[y * w + x + i] = '-';
+            }
+            cells[y * w + x] = 'H';
+        }
+        cells.chunks(w).fold(String::new(), |mut text, row| {
+            text.extend(row);
+            text.push('\n');
+            text
+        })
+    }
+
+    /// Whether each tree is visible from outside the forest, in row major order
+    fn visibility(&self) -> Vec<bool> {
+        let (h, w) = self.0.size();
+        let mut visible = vec![false; h * w];
+        self.sweep_all(|(x, y), sight| visible[y * w + x] |= sight.to_edge);
+        visible
+    }
+
+    /// Scenic score of each tree, together with its position
+    fn scores(&self) -> Scores<'_> {
+        Scores {
+            forest: self,
+            y: 0,
+            columns: vec![Vec::new(); self.0.cols()],
+            ready: Vec::new(),
+        }
     }
 
     fn visible_trees(&self, (x, y): (usize, usize), (dx, dy): (i32, i32)) -> usize {
@@ -69,26 +172,194 @@ impl Forest {
             }
         })
     }
+
+    /// Sweeps every line of trees in the four directions, and reports what each tree sees toward the start of the line
+    fn sweep_all(&self, mut report: impl FnMut((usize, usize), Sight)) {
+        let (h, w) = self.0.size();
+        for y in 0..h {
+            self.sweep((0..w).map(|x| (x, y)), &mut report);
+            self.sweep((0..w).rev().map(|x| (x, y)), &mut report);
+        }
+        for x in 0..w {
+            self.sweep((0..h).map(|y| (x, y)), &mut report);
+            self.sweep((0..h).rev().map(|y| (x, y)), &mut report);
+        }
+    }
+
+    /// Keeps a stack of strictly decreasing trees, so that the top of the stack (after popping the smaller trees)
+    /// is the nearest tree blocking the view
+    fn sweep(
+        &self,
+        line: impl Iterator<Item = (usize, usize)>,
+        report: &mut impl FnMut((usize, usize), Sight),
+    ) {
+        let mut stack: Vec<(usize, Height)> = Vec::new();
+        for (i, (x, y)) in line.enumerate() {
+            let height = self.0[y][x];
+            while matches!(stack.last(), Some(&(_, h)) if h < height) {
+                stack.pop();
+            }
+            let sight = match stack.last() {
+                Some(&(j, _)) => Sight {
+                    distance: i - j,
+                    to_edge: false,
+                },
+                None => Sight {
+                    distance: i,
+                    to_edge: true,
+                },
+            };
+            report((x, y), sight);
+            stack.push((i, height));
+        }
+    }
+}
+
+/// Scenic scores computed one row at a time, without storing a score for every tree
+///
+/// The views left and right are found by sweeping each row.
+/// The views up and down are found with a stack per column, like in [`Forest::sweep`],
+/// except that the trees popped from the stack are the ones whose view down gets blocked.
+struct Scores<'a> {
+    forest: &'a Forest,
+    /// Next row to sweep
+    y: usize,
+    /// `(y, height, product of the other views)` of the trees whose view down is not blocked yet,
+    /// by strictly decreasing height
+    columns: Vec<Vec<(usize, Height, usize)>>,
+    /// Scores already known, but not returned yet
+    ready: Vec<((usize, usize), usize)>,
+}
+
+impl Scores<'_> {
+    fn sweep_row(&mut self) {
+        let (y, forest) = (self.y, self.forest);
+        let w = forest.0.cols();
+        let mut horizontal = vec![1; w];
+        let mut report = |(x, _), sight: Sight| horizontal[x] *= sight.distance;
+        forest.sweep((0..w).map(|x| (x, y)), &mut report);
+        forest.sweep((0..w).rev().map(|x| (x, y)), &mut report);
+        for (x, stack) in self.columns.iter_mut().enumerate() {
+            let height = forest.0[y][x];
+            let mut blocker = None;
+            while matches!(stack.last(), Some(&(_, h, _)) if h <= height) {
+                let (row, h, views) = stack.pop().unwrap();
+                self.ready.push(((x, row), views * (y - row)));
+                blocker = Some((row, h));
+            }
+            let up = match (blocker, stack.last()) {
+                (Some((row, h)), _) if h == height => y - row,
+                (_, Some(&(row, _, _))) => y - row,
+                _ => y,
+            };
+            stack.push((y, height, horizontal[x] * up));
+        }
+    }
+
+    /// The trees left in the stacks can see down to the edge
+    fn sweep_bottom_edge(&mut self) {
+        let edge = self.forest.0.rows() - 1;
+        for (x, stack) in self.columns.iter_mut().enumerate() {
+            self.ready.extend(
+                stack
+                    .drain(..)
+                    .map(|(row, _, views)| ((x, row), views * (edge - row))),
+            );
+        }
+    }
+}
+
+impl Iterator for Scores<'_> {
+    type Item = ((usize, usize), usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(score) = self.ready.pop() {
+                return Some(score);
+            }
+            let rows = self.forest.0.rows();
+            if self.y < rows {
+                self.sweep_row();
+            } else if self.y == rows {
+                self.sweep_bottom_edge();
+            } else {
+                return None;
+            }
+            self.y += 1;
+        }
+    }
+}
+
+/// A candidate spot for the tree house
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct TreeHouse {
+    pub position: (usize, usize),
+    pub score: usize,
+    pub views: Views,
+}
+
+/// Number of trees in view in each direction
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Views {
+    pub up: usize,
+    pub down: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+/// What a tree sees in one direction
+#[derive(Debug, Copy, Clone)]
+struct Sight {
+    /// Number of trees in view
+    distance: usize,
+    /// Whether the view reaches the edge of the forest
+    to_edge: bool,
 }
 
 impl FromStr for Forest {
-    type Err = Infallible;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = s.lines().next().unwrap().len();
-        Ok(Forest(Grid::from_vec(
-            s.lines()
-                .flat_map(|l| l.chars().map(|c| c.to_digit(10).unwrap() as u8))
-                .collect(),
-            width,
-        )))
+        Self::from_rows(s.lines().map(|line| {
+            line.chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .map(|h| h as Height)
+                        .ok_or_else(|| format!("invalid height: {c:?}"))
+                })
+                .collect()
+        }))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::netpbm::{Gray, Grayscale};
+
     use super::*;
 
+    /// Naive tree-by-tree scans, used as a reference for the sweeps
+    fn coords(forest: &Forest) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (h, w) = forest.0.size();
+        (0..w).flat_map(move |x| (0..h).map(move |y| (x, y)))
+    }
+
+    fn is_visible(forest: &Forest, (x, y): (usize, usize)) -> bool {
+        let trees = &forest.0;
+        let value = trees[y][x];
+        (0..y).all(|y| trees[y][x] < value)
+            || ((y + 1)..trees.rows()).all(|y| trees[y][x] < value)
+            || (0..x).all(|x| trees[y][x] < value)
+            || ((x + 1)..trees.cols()).all(|x| trees[y][x] < value)
+    }
+
+    fn score(forest: &Forest, coord: (usize, usize)) -> usize {
+        [(0, 1), (0, -1), (1, 0), (-1, 0)]
+            .into_iter()
+            .map(|step| forest.visible_trees(coord, step))
+            .product()
+    }
+
     const EXAMPLE: &str = r#"
 30373
 25512
@@ -113,6 +384,155 @@ mod tests {
         assert_eq!(part_2(input.trim()), expected);
     }
 
+    #[rstest]
+    #[case::example(EXAMPLE.trim())]
+    #[case::input(INPUT.trim())]
+    #[case::repeated_heights("9119\n1551\n1991\n5115")]
+    #[case::skewed_heights("0000009\n0900000\n0000090")]
+    fn sweeps_should_match_tree_by_tree_scans(#[case] input: &str) {
+        let forest: Forest = input.parse().unwrap();
+        let visible = coords(&forest).filter(|&c| is_visible(&forest, c)).count();
+        let max_score = coords(&forest).map(|c| score(&forest, c)).max().unwrap();
+        let mut scores: Vec<_> = forest.scores().collect();
+        scores.sort_unstable();
+        let mut expected: Vec<_> = coords(&forest).map(|c| (c, score(&forest, c))).collect();
+        expected.sort_unstable();
+        assert_eq!(scores, expected);
+        assert_eq!(forest.count_visible(), visible);
+        assert_eq!(forest.max_score(), max_score);
+    }
+
+    #[rstest]
+    #[case(
+        "30373\n25512\n65332\n33549\n35390",
+        "3 0 3 7 3\n2 5 5 1 2\n6 5 3 3 2\n3 3 5 4 9\n3 5 3 9 0"
+    )]
+    #[case(
+        "30373\n25512\n65332\n33549\n35390",
+        "3,0,3,7,3\n2,5,5,1,2\n6,5,3,3,2\n3,3,5,4,9\n3,5,3,9,0"
+    )]
+    #[case(
+        "30373\n25512\n65332\n33549\n35390",
+        "3, 0, 3, 7, 3\n2, 5, 5, 1, 2\n6, 5, 3, 3, 2\n3, 3, 5, 4, 9\n3, 5, 3, 9, 0"
+    )]
+    #[case("123\n456", "1 2 3\n4 5 6")]
+    #[case("13\n46\n79", "10,30\n40,60\n70,90")]
+    fn separated_format_should_match_digits_format(
+        #[case] digits: Forest,
+        #[case] separated: &str,
+    ) {
+        let separated = Forest::parse_separated(separated).unwrap();
+        assert_eq!(separated.count_visible(), digits.count_visible());
+        assert_eq!(separated.max_score(), digits.max_score());
+    }
+
+    #[rstest]
+    #[case("1000 20 1000\n20 10 20\n1000 20 1000", 8, 1)]
+    #[case("1000 20 1000\n20 1000 20\n300 20 1000", 9, 1)]
+    #[case("65535,1,2,3,65534,1,1", 7, 0)]
+    #[case("1 2\n3 4\n5 6\n7 8", 8, 0)]
+    fn should_support_multi_digit_heights(
+        #[case] input: &str,
+        #[case] expected_visible: usize,
+        #[case] expected_score: usize,
+    ) {
+        let forest = Forest::parse_separated(input).unwrap();
+        assert_eq!(forest.count_visible(), expected_visible);
+        assert_eq!(forest.max_score(), expected_score);
+    }
+
+    #[rstest]
+    #[case("123\n45")]
+    #[case("12\n345")]
+    #[case("")]
+    #[case("12a")]
+    fn should_reject_invalid_forest(#[case] input: &str) {
+        assert!(input.parse::<Forest>().is_err());
+    }
+
+    #[rstest]
+    #[case("1 2 3\n4 5")]
+    #[case("1,2\n3,4,5")]
+    #[case("1 2\n3 65536")]
+    #[case("1 -2")]
+    #[case("")]
+    fn should_reject_invalid_separated_forest(#[case] input: &str) {
+        assert!(Forest::parse_separated(input).is_err());
+    }
+
+    #[test]
+    fn should_find_best_tree_houses() {
+        let forest: Forest = EXAMPLE.trim().parse().unwrap();
+        let best = forest.best_tree_houses(2);
+        assert_eq!(
+            best,
+            vec![
+                TreeHouse {
+                    position: (2, 3),
+                    score: 8,
+                    views: Views {
+                        up: 2,
+                        down: 1,
+                        left: 2,
+                        right: 2
+                    },
+                },
+                TreeHouse {
+                    position: (1, 2),
+                    score: 6,
+                    views: Views {
+                        up: 1,
+                        down: 2,
+                        left: 1,
+                        right: 3
+                    },
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::example(EXAMPLE.trim())]
+    #[case::input(INPUT.trim())]
+    fn best_tree_houses_should_be_consistent_with_scores(#[case] input: &str) {
+        let forest: Forest = input.parse().unwrap();
+        let best = forest.best_tree_houses(10);
+        assert_eq!(best.len(), 10);
+        assert_eq!(best[0].score, forest.max_score());
+        assert!(best.windows(2).all(|w| w[0].score >= w[1].score));
+        for tree_house in best {
+            let Views {
+                up,
+                down,
+                left,
+                right,
+            } = tree_house.views;
+            assert_eq!(tree_house.score, up * down * left * right);
+            assert_eq!(tree_house.score, score(&forest, tree_house.position));
+        }
+    }
+
+    #[test]
+    fn should_render_forest_with_tree_house() {
+        let forest: Forest = EXAMPLE.trim().parse().unwrap();
+        assert_eq!(forest.render(None), "#####\n###.#\n##.##\n#.#.#\n#####\n");
+        let best = forest.best_tree_houses(1);
+        assert_eq!(
+            forest.render(best.first()),
+            "#####\n##|.#\n##|##\n--H--\n##|##\n"
+        );
+    }
+
+    #[test]
+    fn should_export_heights_as_grayscale() {
+        let forest: Forest = EXAMPLE.trim().parse().unwrap();
+        let image = forest.image(&Grayscale { min: 0, max: 9 });
+        assert_eq!((image.width(), image.height()), (5, 5));
+        assert_eq!(image.get((0, 0)), Some(Gray(85)));
+        assert_eq!(image.get((4, 2)), Some(Gray(56)));
+        assert_eq!(image.get((3, 4)), Some(Gray(255)));
+    }
+
     #[rstest]
     #[case("1", (0, 0), true)]
     #[case("12\n34", (0, 0), true)]
@@ -127,7 +547,7 @@ mod tests {
         #[case] coord: (usize, usize),
         #[case] expected: bool,
     ) {
-        assert_eq!(forest.is_visible(coord), expected);
+        assert_eq!(is_visible(&forest, coord), expected);
     }
 
     #[rstest]
@@ -139,7 +559,7 @@ mod tests {
         #[case] coord: (usize, usize),
         #[case] expected: usize,
     ) {
-        assert_eq!(forest.score(coord), expected);
+        assert_eq!(score(&forest, coord), expected);
     }
 
     #[rstest]
@@ -1,8 +1,10 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
-use std::iter;
+use std::io;
 use std::str::FromStr;
 
+use crate::animation::{Frame, Viewport};
+
 type Output = usize;
 
 pub fn part_1(input: &str) -> Output {
@@ -14,24 +16,38 @@ pub fn part_2(input: &str) -> Output {
 }
 
 fn solve(input: &str, rope_len: usize) -> Output {
+    simulate(input, Rope::new(rope_len)).tail_visits_count()
+}
+
+/// Simulates a rope of `rope_len` knots while recording the path of every knot
+pub fn record(input: &str, rope_len: usize) -> Recording {
+    simulate(input, Rope::new(rope_len).recorded())
+        .into_recording()
+        .unwrap()
+}
+
+/// Applies every move of the `input` to the `rope`
+pub fn simulate<F: Follow>(input: &str, rope: Rope<F>) -> Rope<F> {
     input
         .lines()
         .map(|l| l.split_once(' ').unwrap())
         .filter_map(|(d, n)| Some((d.parse::<Direction>().ok()?, n.parse().ok()?)))
-        .flat_map(|(d, n)| iter::repeat(d).take(n))
-        .fold(Rope::new(rope_len), |mut rope, direction| {
-            rope.move_head(direction);
+        .fold(rope, |mut rope, (direction, steps)| {
+            rope.move_head_by(direction, steps);
             rope
         })
-        .tail_visits_count()
 }
 
 #[derive(Debug, Copy, Clone)]
-enum Direction {
+pub enum Direction {
     Right,
     Left,
     Up,
     Down,
+    UpRight,
+    UpLeft,
+    DownRight,
+    DownLeft,
 }
 
 impl FromStr for Direction {
@@ -43,6 +59,10 @@ impl FromStr for Direction {
             "L" => Direction::Left,
             "U" => Direction::Up,
             "D" => Direction::Down,
+            "UR" => Direction::UpRight,
+            "UL" => Direction::UpLeft,
+            "DR" => Direction::DownRight,
+            "DL" => Direction::DownLeft,
             d => return Err(format!("unknown direction: {d}")),
         })
     }
@@ -55,50 +75,346 @@ impl From<Direction> for (i32, i32) {
             Direction::Left => (-1, 0),
             Direction::Up => (0, 1),
             Direction::Down => (0, -1),
+            Direction::UpRight => (1, 1),
+            Direction::UpLeft => (-1, 1),
+            Direction::DownRight => (1, -1),
+            Direction::DownLeft => (-1, -1),
         }
     }
 }
 
-struct Rope {
-    tail_visits: HashSet<(i32, i32)>,
+/// How a knot follows the knot in front of it
+pub trait Follow {
+    /// Returns the move of a knot, given the `offset` from that knot to the knot in front of it
+    fn follow(&self, offset: (i32, i32)) -> (i32, i32);
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Metric {
+    /// Diagonal neighbours are touching, and knots may move diagonally
+    Chebyshev,
+    /// Only orthogonal neighbours are touching, and knots move along one axis at a time
+    Manhattan,
+}
+
+/// Moves a knot by one step toward the knot in front of it when they are more than `slack` apart
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FollowRule {
+    pub metric: Metric,
+    pub slack: u32,
+}
+
+impl Default for FollowRule {
+    fn default() -> Self {
+        Self {
+            metric: Metric::Chebyshev,
+            slack: 1,
+        }
+    }
+}
+
+impl Follow for FollowRule {
+    fn follow(&self, (dx, dy): (i32, i32)) -> (i32, i32) {
+        match self.metric {
+            Metric::Chebyshev if dx.unsigned_abs().max(dy.unsigned_abs()) > self.slack => {
+                (dx.signum(), dy.signum())
+            }
+            Metric::Manhattan if dx.unsigned_abs() + dy.unsigned_abs() > self.slack => {
+                if dx.abs() >= dy.abs() {
+                    (dx.signum(), 0)
+                } else {
+                    (0, dy.signum())
+                }
+            }
+            _ => (0, 0),
+        }
+    }
+}
+
+pub struct Rope<F = FollowRule> {
+    tail_visits: VisitedCells,
     knots: Vec<(i32, i32)>,
+    rule: F,
+    recording: Option<Recording>,
 }
 
 impl Rope {
-    fn new(len: usize) -> Self {
-        let mut tail_positions = HashSet::new();
+    pub fn new(len: usize) -> Self {
+        Self::with_rule(len, FollowRule::default())
+    }
+}
+
+impl<F: Follow> Rope<F> {
+    pub fn with_rule(len: usize, rule: F) -> Self {
+        let mut tail_positions = VisitedCells::default();
         tail_positions.insert((0, 0));
         Self {
             tail_visits: tail_positions,
             knots: vec![(0, 0); len],
+            rule,
+            recording: None,
+        }
+    }
+
+    /// Records the position of every knot after each step
+    pub fn recorded(mut self) -> Self {
+        self.recording = Some(Recording {
+            steps: vec![self.knots.clone()],
+        });
+        self
+    }
+
+    pub fn into_recording(self) -> Option<Recording> {
+        self.recording
+    }
+
+    /// Current position of every knot, starting with the head
+    pub fn knots(&self) -> &[(i32, i32)] {
+        &self.knots
+    }
+
+    /// Moves the head `steps` times in the given `direction`
+    ///
+    /// As soon as the whole rope moves in one piece, the remaining steps are applied all at once.
+    pub fn move_head_by(&mut self, direction: Direction, steps: usize) {
+        for done in 1..=steps {
+            if self.move_head(direction) && self.recording.is_none() {
+                self.translate(direction, steps - done);
+                return;
+            }
         }
     }
 
-    fn move_head(&mut self, direction: Direction) {
+    /// Moves the head by one step, and returns true if every knot moved the same way as the head
+    fn move_head(&mut self, direction: Direction) -> bool {
         let (dx, dy) = direction.into();
         self.knots[0].0 += dx;
         self.knots[0].1 += dy;
+        let mut taut = true;
         for i in 1..self.knots.len() {
             let head = self.knots[i - 1];
-            Self::update_tail_knot(&mut self.knots[i], head);
+            let before = self.knots[i];
+            follow(&self.rule, &mut self.knots[i], head);
+            taut &= self.knots[i] == (before.0 + dx, before.1 + dy);
         }
         self.tail_visits.insert(*self.knots.last().unwrap());
+        if let Some(recording) = &mut self.recording {
+            recording.steps.push(self.knots.clone());
+        }
+        taut
     }
 
-    fn update_tail_knot((x, y): &mut (i32, i32), (hx, hy): (i32, i32)) {
-        if (hx - *x).abs() > 1 || (hy - *y).abs() > 1 {
-            *x += (hx - *x).clamp(-1, 1);
-            *y += (hy - *y).clamp(-1, 1);
+    /// Moves every knot `steps` times in the given `direction`
+    fn translate(&mut self, direction: Direction, steps: usize) {
+        let (dx, dy) = direction.into();
+        let (tx, ty) = *self.knots.last().unwrap();
+        for i in 1..=(steps as i32) {
+            self.tail_visits.insert((tx + dx * i, ty + dy * i));
+        }
+        let steps = steps as i32;
+        for (x, y) in &mut self.knots {
+            *x += dx * steps;
+            *y += dy * steps;
         }
     }
 
-    fn tail_visits_count(&self) -> Output {
+    /// Number of distinct cells visited by the tail
+    pub fn tail_visits_count(&self) -> Output {
         self.tail_visits.len()
     }
 }
 
+fn follow(rule: &impl Follow, (x, y): &mut (i32, i32), (hx, hy): (i32, i32)) {
+    let (dx, dy) = rule.follow((hx - *x, hy - *y));
+    *x += dx;
+    *y += dy;
+}
+
+/// Set of cells stored as 64x64 bitmaps, allocated only where cells are visited
+#[derive(Default)]
+struct VisitedCells {
+    chunks: HashMap<(i32, i32), Box<[u64; 64]>>,
+    len: usize,
+}
+
+impl VisitedCells {
+    fn insert(&mut self, (x, y): (i32, i32)) -> bool {
+        let rows = self
+            .chunks
+            .entry((x >> 6, y >> 6))
+            .or_insert_with(|| Box::new([0; 64]));
+        let row = &mut rows[(y & 63) as usize];
+        let bit = 1 << (x & 63);
+        let inserted = *row & bit == 0;
+        if inserted {
+            *row |= bit;
+            self.len += 1;
+        }
+        inserted
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Position of every knot of a rope after each step, starting with the initial position
+pub struct Recording {
+    steps: Vec<Vec<(i32, i32)>>,
+}
+
+impl Recording {
+    /// Number of recorded steps, not including the initial position
+    pub fn len(&self) -> usize {
+        self.steps.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Position of the knot at index `knot` (the head being at index 0) after each step
+    pub fn trajectory(&self, knot: usize) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.steps.iter().map(move |knots| knots[knot])
+    }
+
+    /// Cells visited by the tail up to the given `step` (included)
+    pub fn tail_visits(&self, step: usize) -> HashSet<(i32, i32)> {
+        self.steps[..=step]
+            .iter()
+            .filter_map(|knots| knots.last().copied())
+            .collect()
+    }
+
+    /// Smallest and biggest coordinates reached by any knot during the whole recording
+    fn bounds(&self) -> ((i32, i32), (i32, i32)) {
+        self.steps.iter().flatten().fold(
+            ((0, 0), (0, 0)),
+            |((min_x, min_y), (max_x, max_y)), &(x, y)| {
+                ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)))
+            },
+        )
+    }
+
+    /// Labels of the knots at the given `step`, in the same way as in the puzzle diagrams
+    fn labelled_knots(&self, step: usize) -> impl Iterator<Item = (char, (i32, i32))> + '_ {
+        let knots = &self.steps[step];
+        knots
+            .iter()
+            .copied()
+            .enumerate()
+            .rev()
+            .map(move |(i, knot)| (knot_label(i, knots.len()), knot))
+    }
+
+    /// Renders the rope at the given `step` as in the puzzle diagrams
+    ///
+    /// Cells visited by the tail so far are marked with `#`, and the starting position with `s`.
+    pub fn render_text(&self, step: usize) -> String {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds();
+        let width = (max_x - min_x + 1) as usize;
+        let mut rows = vec![vec!['.'; width]; (max_y - min_y + 1) as usize];
+        let mut set = |(x, y): (i32, i32), c: char| {
+            rows[(max_y - y) as usize][(x - min_x) as usize] = c;
+        };
+        for visit in self.tail_visits(step) {
+            set(visit, '#');
+        }
+        set((0, 0), 's');
+        for (label, knot) in self.labelled_knots(step) {
+            set(knot, label);
+        }
+        rows.into_iter().fold(String::new(), |mut text, row| {
+            text.extend(row);
+            text.push('\n');
+            text
+        })
+    }
+
+    /// Renders the rope at the given `step` in the `viewport`, with the same symbols as [`Self::render_text`]
+    pub fn frame(&self, step: usize, viewport: &Viewport) -> Frame {
+        self.draw(step, &self.tail_visits(step), viewport)
+    }
+
+    /// Renders every step in a `width` x `height` viewport following the head
+    pub fn frames(&self, width: usize, height: usize) -> impl Iterator<Item = Frame> + '_ {
+        let mut viewport = Viewport::new(width, height)
+            .with_margin(width.min(height) / 4)
+            .centered_on((0, 0));
+        let mut visits = HashSet::new();
+        self.steps.iter().enumerate().map(move |(step, knots)| {
+            visits.extend(knots.last().copied());
+            let (x, y) = knots[0];
+            viewport.follow((x.into(), y.into()));
+            self.draw(step, &visits, &viewport)
+        })
+    }
+
+    fn draw(&self, step: usize, visits: &HashSet<(i32, i32)>, viewport: &Viewport) -> Frame {
+        let knots: HashMap<_, _> = self
+            .labelled_knots(step)
+            .map(|(label, knot)| (knot, label))
+            .collect();
+        viewport.render(|(x, y)| {
+            let cell = (x as i32, y as i32);
+            match knots.get(&cell) {
+                Some(&label) => label,
+                None if cell == (0, 0) => 's',
+                None if visits.contains(&cell) => '#',
+                None => '.',
+            }
+        })
+    }
+
+    /// Writes the rope at the given `step` as an SVG image with one square unit per cell
+    pub fn write_svg(&self, step: usize, mut out: impl io::Write) -> io::Result<()> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds();
+        let (width, height) = (max_x - min_x + 1, max_y - min_y + 1);
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{}" height="{}">"#,
+            width * 10,
+            height * 10
+        )?;
+        writeln!(
+            out,
+            r#"<rect width="{width}" height="{height}" fill="white"/>"#
+        )?;
+        let mut visits: Vec<_> = self.tail_visits(step).into_iter().collect();
+        visits.sort_unstable();
+        for (x, y) in visits {
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="1" height="1" fill="lightgray"/>"#,
+                x - min_x,
+                max_y - y
+            )?;
+        }
+        for (label, (x, y)) in self.labelled_knots(step) {
+            let color = if label == 'H' { "red" } else { "steelblue" };
+            writeln!(
+                out,
+                r#"<circle cx="{}.5" cy="{}.5" r="0.4" fill="{color}"><title>{label}</title></circle>"#,
+                x - min_x,
+                max_y - y
+            )?;
+        }
+        writeln!(out, "</svg>")
+    }
+}
+
+fn knot_label(index: usize, rope_len: usize) -> char {
+    match index {
+        0 => 'H',
+        _ if index == rope_len - 1 && rope_len == 2 => 'T',
+        _ => char::from_digit(index as u32, 36).unwrap_or('*'),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::iter;
+
     use super::*;
 
     const EXAMPLE: &str = r#"
@@ -158,6 +474,161 @@ U 20
         assert_eq!(solve(instructions, len), expected);
     }
 
+    #[rstest]
+    #[case(0, "......\n......\n......\n......\nH.....\n")]
+    #[case(4, "......\n......\n......\n......\ns##TH.\n")]
+    #[case(24, "..##..\n...##.\n.TH##.\n....#.\ns###..\n")]
+    fn should_render_recorded_steps(#[case] step: usize, #[case] expected: &str) {
+        let recording = record(EXAMPLE.trim(), 2);
+        assert_eq!(recording.len(), 24);
+        assert_eq!(recording.render_text(step), expected);
+    }
+
+    #[test]
+    fn should_record_trajectory_of_every_knot() {
+        let recording = record("R 2\nU 2", 3);
+        let head: Vec<_> = recording.trajectory(0).collect();
+        assert_eq!(head, vec![(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)]);
+        let tail: Vec<_> = recording.trajectory(2).collect();
+        assert_eq!(tail, vec![(0, 0), (0, 0), (0, 0), (0, 0), (1, 1)]);
+        assert_eq!(
+            recording.tail_visits(recording.len()).len(),
+            solve("R 2\nU 2", 3)
+        );
+    }
+
+    #[rstest]
+    #[case(0)]
+    #[case(4)]
+    #[case(24)]
+    fn frame_should_match_text_rendering(#[case] step: usize) {
+        let recording = record(EXAMPLE.trim(), 2);
+        let viewport = Viewport::new(6, 5);
+        assert_eq!(
+            recording.frame(step, &viewport).to_string(),
+            recording.render_text(step)
+        );
+    }
+
+    #[test]
+    fn frames_should_follow_head() {
+        let recording = record(LARGER_EXAMPLE.trim(), 10);
+        let frames: Vec<_> = recording.frames(9, 7).collect();
+        assert_eq!(frames.len(), recording.len() + 1);
+        assert!(frames.iter().all(|f| f.width() == 9 && f.height() == 7));
+        assert!(frames.iter().all(|f| f.to_string().contains('H')));
+        assert_eq!(
+            frames.last().unwrap(),
+            &recording.frame(recording.len(), &Viewport::new(9, 7).with_origin((-12, 10)))
+        );
+    }
+
+    #[test]
+    fn should_export_svg() {
+        let recording = record(LARGER_EXAMPLE.trim(), 10);
+        let mut svg = Vec::new();
+        recording.write_svg(recording.len(), &mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("lightgray").count(), 36);
+        assert_eq!(svg.matches("<circle").count(), 10);
+    }
+
+    #[rstest]
+    #[case(2)]
+    #[case(3)]
+    #[case(10)]
+    fn fast_path_should_match_step_by_step_simulation(#[case] rope_len: usize) {
+        let mut seed = 42_u32;
+        let moves: Vec<_> = iter::repeat_with(|| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let direction = ["R", "L", "U", "D", "UR", "UL", "DR", "DL"][(seed >> 16) as usize % 8];
+            format!("{direction} {}", (seed >> 20) % 40)
+        })
+        .take(500)
+        .collect();
+        let input = moves.join("\n");
+
+        let mut visits = HashSet::from([(0, 0)]);
+        let mut rope = Rope::new(rope_len);
+        for (direction, steps) in input.lines().map(|l| l.split_once(' ').unwrap()) {
+            for _ in 0..steps.parse().unwrap() {
+                rope.move_head(direction.parse().unwrap());
+                visits.insert(*rope.knots.last().unwrap());
+            }
+        }
+
+        assert_eq!(solve(&input, rope_len), visits.len());
+    }
+
+    #[test]
+    fn should_handle_huge_moves() {
+        assert_eq!(solve("R 5000000\nU 3000000\nL 1000000", 10), 8_999_974);
+    }
+
+    #[test]
+    fn visited_cells_should_support_negative_coordinates() {
+        let mut cells = VisitedCells::default();
+        for cell in [(0, 0), (-1, 0), (0, -1), (-64, -65), (63, 64), (-1, 0)] {
+            cells.insert(cell);
+        }
+        assert_eq!(cells.len(), 5);
+    }
+
+    #[rstest]
+    #[case("UR 3", 3)]
+    #[case("UR 3\nDL 3", 3)]
+    #[case("UR 2\nR 2", 4)]
+    #[case("DR 4\nUL 1\nU 3", 6)]
+    fn should_move_diagonally(#[case] input: &str, #[case] expected: Output) {
+        assert_eq!(solve(input, 2), expected);
+    }
+
+    #[rstest]
+    #[case(Metric::Chebyshev, 1, (1, 1), (0, 0))]
+    #[case(Metric::Chebyshev, 1, (2, 1), (1, 1))]
+    #[case(Metric::Chebyshev, 2, (2, 1), (0, 0))]
+    #[case(Metric::Chebyshev, 2, (3, -1), (1, -1))]
+    #[case(Metric::Chebyshev, 0, (1, 0), (1, 0))]
+    #[case(Metric::Manhattan, 1, (1, 0), (0, 0))]
+    #[case(Metric::Manhattan, 1, (1, 1), (1, 0))]
+    #[case(Metric::Manhattan, 1, (1, -2), (0, -1))]
+    #[case(Metric::Manhattan, 3, (2, 1), (0, 0))]
+    #[case(Metric::Manhattan, 3, (-2, -2), (-1, 0))]
+    fn should_follow_according_to_rule(
+        #[case] metric: Metric,
+        #[case] slack: u32,
+        #[case] offset: (i32, i32),
+        #[case] expected: (i32, i32),
+    ) {
+        assert_eq!(FollowRule { metric, slack }.follow(offset), expected);
+    }
+
+    #[test]
+    fn should_accept_custom_follow_rule() {
+        struct Rigid;
+        impl Follow for Rigid {
+            fn follow(&self, (dx, dy): (i32, i32)) -> (i32, i32) {
+                (dx - dx.signum(), dy - dy.signum())
+            }
+        }
+        let rope = simulate("R 4\nU 2", Rope::with_rule(2, Rigid));
+        assert_eq!(rope.knots(), &[(4, 2), (3, 1)]);
+        assert_eq!(rope.tail_visits_count(), 5);
+    }
+
+    #[test]
+    fn should_count_visits_with_manhattan_rope() {
+        let rule = FollowRule {
+            metric: Metric::Manhattan,
+            slack: 1,
+        };
+        let rope = simulate("R 2\nU 2", Rope::with_rule(2, rule));
+        assert_eq!(rope.knots(), &[(2, 2), (2, 1)]);
+        assert_eq!(rope.tail_visits_count(), 4);
+    }
+
     #[rstest]
     #[case((0,0), (0,0))]
     #[case((1,0), (0,0))]
@@ -183,7 +654,7 @@ U 20
     #[case((2,-2), (1, -1))]
     fn should_move_tail_towards_head(#[case] head: (i32, i32), #[case] expected: (i32, i32)) {
         let mut tail = (0, 0);
-        Rope::update_tail_knot(&mut tail, head);
+        follow(&FollowRule::default(), &mut tail, head);
         assert_eq!(tail, expected);
     }
 }
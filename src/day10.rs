@@ -1,5 +1,7 @@
 use std::{iter, str::FromStr};
 
+use crate::netpbm::{ColorMap, Image};
+
 type Output = i32;
 
 pub fn part_1(input: &str) -> Output {
@@ -15,22 +17,32 @@ pub fn part_1(input: &str) -> Output {
 }
 
 pub fn part_2(input: &str) -> String {
-    let mut device = init_device(input);
     let mut result = String::with_capacity(41 * 6);
-    for _ in 0..6 {
-        for col in 0..40 {
-            if (col - device.register_value()).abs() < 2 {
-                result.push('#');
-            } else {
-                result.push('.');
-            }
-            device.advance(1);
-        }
+    for row in screen(input) {
+        result.extend(row.map(|lit| if lit { '#' } else { '.' }));
         result.push('\n');
     }
     result
 }
 
+/// Image of the CRT screen, with one pixel per lit (`true`) or dark (`false`) CRT pixel
+pub fn crt_image<C: ColorMap<bool>>(input: &str, colors: &C) -> Image<C::Pixel> {
+    let screen = screen(input);
+    Image::from_fn(40, 6, |(x, y)| colors.color(screen[y][x]))
+}
+
+fn screen(input: &str) -> [[bool; 40]; 6] {
+    let mut device = init_device(input);
+    let mut screen = [[false; 40]; 6];
+    for row in &mut screen {
+        for (col, pixel) in (0..).zip(row.iter_mut()) {
+            *pixel = (col - device.register_value()).abs() < 2;
+            device.advance(1);
+        }
+    }
+    screen
+}
+
 fn init_device(input: &str) -> Device {
     let instructions: Vec<Instruction> = input
         .lines()
@@ -101,6 +113,8 @@ impl FromStr for Instruction {
 
 #[cfg(test)]
 mod tests {
+    use crate::netpbm::Gray;
+
     use super::Instruction::*;
     use super::*;
 
@@ -143,6 +157,19 @@ mod tests {
         assert_eq!(result, format!("{}\n", expected.trim()));
     }
 
+    #[test]
+    fn crt_image_should_match_text_output() {
+        let text = part_2(EXAMPLE.trim());
+        let image = crt_image(EXAMPLE.trim(), &|lit| Gray(if lit { 255 } else { 0 }));
+        assert_eq!((image.width(), image.height()), (40, 6));
+        for (y, row) in text.lines().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let expected = if c == '#' { Gray(255) } else { Gray(0) };
+                assert_eq!(image.get((x, y)), Some(expected));
+            }
+        }
+    }
+
     #[rstest]
     #[case(1, vec![], 0, 1)]
     #[case(1, vec![AddX(3)], 2, 4)]
@@ -1,4 +1,6 @@
-use std::{collections::HashMap, convert::Infallible, fmt::Debug, str::FromStr};
+use std::{fmt::Debug, io, iter::Product, mem, str::FromStr};
+
+use crate::{cycle, math};
 
 type Output = usize;
 type WorryLevel = u64;
@@ -8,44 +10,270 @@ pub fn part_1(input: &str) -> Output {
 }
 
 pub fn part_2(input: &str) -> Output {
-    solve(input, 1, 10_000)
+    Output::try_from(solve_extrapolated(input, 10_000)).expect("monkey business overflows")
 }
 
 fn solve(input: &str, worry_level_divisor: WorryLevel, rounds: u32) -> Output {
-    let mut monkeys = parse_monkeys(input);
-    let mut ids: Vec<_> = monkeys.keys().copied().collect();
-    ids.sort();
-    let max_worry_level: WorryLevel = monkeys.values().map(|m| m.test_divisor).product();
+    let mut troop: Troop = input.parse().unwrap();
     for _ in 0..rounds {
-        for id in ids.iter().copied() {
-            let mut monkey = monkeys.remove(&id).unwrap();
-            for (target, item) in monkey.throw_all(worry_level_divisor) {
-                monkeys
-                    .get_mut(&target)
-                    .unwrap()
-                    .catch(item % max_worry_level);
-            }
-            monkeys.insert(id, monkey);
-        }
+        troop.play_round(worry_level_divisor);
     }
-    let mut inspections: Vec<_> = monkeys.values().map(|m| m.inspected).collect();
+    monkey_business(troop.inspections())
+}
+
+/// Same as [`solve`] without worry relief, but extrapolated from the cycle of each item instead of simulating every round
+fn solve_extrapolated(input: &str, rounds: u64) -> u128 {
+    let troop: Troop = input.parse().unwrap();
+    monkey_business(troop.inspections_after(rounds).into_iter().map(u128::from))
+}
+
+fn monkey_business<T: Ord + Product>(inspections: impl IntoIterator<Item = T>) -> T {
+    let mut inspections: Vec<_> = inspections.into_iter().collect();
     inspections.sort();
     inspections.into_iter().rev().take(2).product()
 }
 
-fn parse_monkeys(input: &str) -> HashMap<usize, Monkey> {
-    input
-        .split("\n\n")
-        .map(|monkey| {
-            let (id, declaration) = monkey.split_once('\n').unwrap();
-            let id = id
-                .strip_prefix("Monkey ")
-                .and_then(|m| m.strip_suffix(':'))
-                .and_then(|m| m.parse::<usize>().ok())
-                .unwrap();
-            (id, declaration.trim().parse::<Monkey>().unwrap())
+/// Monkeys indexed by their id
+///
+/// Ids are guaranteed to be contiguous and every throw target to exist.
+pub struct Troop {
+    monkeys: Vec<Monkey>,
+    max_worry_level: WorryLevel,
+    round: u32,
+}
+
+impl Troop {
+    /// Let every monkey take its turn, and returns how many items each monkey inspected during the round
+    pub fn play_round(&mut self, worry_level_divisor: WorryLevel) -> Vec<usize> {
+        self.play_observed_round(worry_level_divisor, &mut ())
+    }
+
+    /// Same as [`Troop::play_round`], but reports every throw and the end of the round to the `observer`
+    pub fn play_observed_round(
+        &mut self,
+        worry_level_divisor: WorryLevel,
+        observer: &mut impl Observer,
+    ) -> Vec<usize> {
+        self.round += 1;
+        let inspections = (0..self.monkeys.len())
+            .map(|id| self.play_turn(id, worry_level_divisor, observer))
+            .collect();
+        observer.on_round_end(self.round, self);
+        inspections
+    }
+
+    fn play_turn(
+        &mut self,
+        id: usize,
+        worry_level_divisor: WorryLevel,
+        observer: &mut impl Observer,
+    ) -> usize {
+        let mut items = mem::take(&mut self.monkeys[id].items);
+        let inspected = items.len();
+        self.monkeys[id].inspected += inspected;
+        for item in items.drain(..) {
+            let (target, item) = self.monkeys[id].inspect(item, worry_level_divisor);
+            observer.on_throw(self.round, id, target, item);
+            self.monkeys[target].catch(item % self.max_worry_level);
+        }
+        self.monkeys[id].items = items;
+        inspected
+    }
+
+    /// Worry levels of the items currently held by each monkey
+    pub fn items(&self) -> impl Iterator<Item = &[WorryLevel]> + '_ {
+        self.monkeys.iter().map(|m| m.items.as_slice())
+    }
+
+    /// Total number of items inspected by each monkey since the beginning
+    pub fn inspections(&self) -> impl Iterator<Item = usize> + '_ {
+        self.monkeys.iter().map(|m| m.inspected)
+    }
+
+    /// Total number of items inspected by each monkey after `rounds` more rounds without worry relief,
+    /// including the inspections already counted by [`Troop::inspections`]
+    ///
+    /// Instead of simulating every round, it follows each item independently until its `(monkey, worry level)`
+    /// repeats, which makes it possible to compute the result for astronomically large round counts.
+    ///
+    /// Panics if an inspection count overflows `u64`.
+    pub fn inspections_after(&self, rounds: u64) -> Vec<u64> {
+        let mut inspections: Vec<_> = self.inspections().map(|i| i as u64).collect();
+        for (monkey, items) in self.monkeys.iter().map(|m| &m.items).enumerate() {
+            for item in items.iter().copied() {
+                let run = cycle::detect_hashing(
+                    (
+                        (monkey, item % self.max_worry_level),
+                        vec![0; self.monkeys.len()],
+                    ),
+                    |(state, visits)| {
+                        let (next, monkeys) = self.item_round(*state);
+                        monkeys.into_iter().for_each(|m| visits[m] += 1);
+                        *state = next;
+                    },
+                    |(state, _)| *state,
+                    |(_, visits)| visits.clone(),
+                );
+                for (total, visits) in inspections
+                    .iter_mut()
+                    .zip(run.metric_at(rounds).expect("inspection count overflows"))
+                {
+                    *total += visits;
+                }
+            }
+        }
+        inspections
+    }
+
+    /// Follows a single item during one round, and returns its state at the start of the next round
+    /// together with the monkeys that inspected it
+    fn item_round(
+        &self,
+        (mut monkey, mut worry_level): (usize, WorryLevel),
+    ) -> ((usize, WorryLevel), Vec<usize>) {
+        let mut visits = Vec::new();
+        loop {
+            visits.push(monkey);
+            let (target, new_level) = self.monkeys[monkey].inspect(worry_level, 1);
+            worry_level = new_level % self.max_worry_level;
+            if target < monkey {
+                return ((target, worry_level), visits);
+            }
+            monkey = target;
+        }
+    }
+}
+
+/// Hook notified of everything happening during [`Troop::play_observed_round`]
+///
+/// Thrown worry levels are the ones computed by the monkey. The troop then keeps them modulo the lcm of the
+/// test divisors, which is what [`Troop::items`] reports.
+pub trait Observer {
+    fn on_throw(&mut self, _round: u32, _from: usize, _to: usize, _worry_level: WorryLevel) {}
+    fn on_round_end(&mut self, _round: u32, _troop: &Troop) {}
+}
+
+impl Observer for () {}
+
+/// Observer printing the state after each round the same way the puzzle text does
+///
+/// Once a write fails, the following rounds are not reported anymore, and the error is returned by [`Reporter::finish`].
+pub struct Reporter<W> {
+    out: W,
+    report: Report,
+    error: Option<io::Error>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Report {
+    /// Items held by each monkey, as in the first part of the puzzle
+    Items,
+    /// Inspections made by each monkey since the beginning, as in the second part of the puzzle
+    Inspections,
+}
+
+impl Reporter<io::Stdout> {
+    pub fn stdout(report: Report) -> Self {
+        Self::new(io::stdout(), report)
+    }
+}
+
+impl<W: io::Write> Reporter<W> {
+    pub fn new(out: W, report: Report) -> Self {
+        Self {
+            out,
+            report,
+            error: None,
+        }
+    }
+
+    /// Returns the writer, or the first error that occurred while reporting
+    pub fn finish(self) -> io::Result<W> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.out),
+        }
+    }
+
+    fn write_round(&mut self, round: u32, troop: &Troop) -> io::Result<()> {
+        match self.report {
+            Report::Items => {
+                writeln!(
+                    self.out,
+                    "After round {round}, the monkeys are holding items with these worry levels:"
+                )?;
+                for (id, items) in troop.items().enumerate() {
+                    let items = items.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    writeln!(self.out, "Monkey {id}: {}", items.join(", "))?;
+                }
+            }
+            Report::Inspections => {
+                writeln!(self.out, "== After round {round} ==")?;
+                for (id, inspected) in troop.inspections().enumerate() {
+                    writeln!(self.out, "Monkey {id} inspected items {inspected} times.")?;
+                }
+            }
+        }
+        writeln!(self.out)
+    }
+}
+
+impl<W: io::Write> Observer for Reporter<W> {
+    fn on_round_end(&mut self, round: u32, troop: &Troop) {
+        if self.error.is_none() {
+            self.error = self.write_round(round, troop).err();
+        }
+    }
+}
+
+impl FromStr for Troop {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut monkeys = s
+            .split("\n\n")
+            .map(parse_monkey)
+            .collect::<Result<Vec<_>, _>>()?;
+        monkeys.sort_by_key(|(id, _)| *id);
+        if let Some((expected, (id, _))) = monkeys.iter().enumerate().find(|(i, (id, _))| i != id) {
+            return Err(format!("expected monkey {expected} but found monkey {id}"));
+        }
+        let monkeys: Vec<_> = monkeys.into_iter().map(|(_, m)| m).collect();
+        for (id, monkey) in monkeys.iter().enumerate() {
+            let (if_true, if_false) = monkey.targets;
+            for target in [if_true, if_false] {
+                if target == id {
+                    return Err(format!("monkey {id} throws to itself"));
+                }
+                if target >= monkeys.len() {
+                    return Err(format!("monkey {id} throws to unknown monkey {target}"));
+                }
+            }
+        }
+        let max_worry_level = math::lcm_all(monkeys.iter().map(|m| m.test_divisor))
+            .ok_or("the lcm of the test divisors overflows")?;
+        Ok(Self {
+            monkeys,
+            max_worry_level,
+            round: 0,
         })
-        .collect()
+    }
+}
+
+fn parse_monkey(monkey: &str) -> Result<(usize, Monkey), String> {
+    let (id, declaration) = monkey
+        .split_once('\n')
+        .ok_or_else(|| format!("invalid monkey: {monkey}"))?;
+    let id = id
+        .strip_prefix("Monkey ")
+        .and_then(|m| m.strip_suffix(':'))
+        .and_then(|m| m.parse::<usize>().ok())
+        .ok_or_else(|| format!("invalid monkey header: {id}"))?;
+    let monkey = declaration
+        .trim()
+        .parse::<Monkey>()
+        .map_err(|e| format!("monkey {id}: {e}"))?;
+    Ok((id, monkey))
 }
 
 struct Monkey {
@@ -68,28 +296,18 @@ impl Monkey {
         self.items.push(item);
     }
 
-    fn throw_all(
-        &mut self,
-        worry_reduction_rate: WorryLevel,
-    ) -> impl Iterator<Item = (usize, WorryLevel)> + '_ {
-        self.inspected += self.items.len();
-        let operator = self.operator;
-        let operand = self.operand;
-        let test_divisor = self.test_divisor;
-        let (target_if_true, target_if_false) = self.targets;
-        self.items.drain(..).map(move |i| {
-            let operand = match operand {
-                Operand::Value(v) => v,
-                Operand::Old => i,
-            };
-            let worry_level = operator.exec(i, operand) / worry_reduction_rate;
-            let target_monkey = if worry_level % test_divisor == 0 {
-                target_if_true
-            } else {
-                target_if_false
-            };
-            (target_monkey, worry_level)
-        })
+    fn inspect(&self, item: WorryLevel, worry_reduction_rate: WorryLevel) -> (usize, WorryLevel) {
+        let operand = match self.operand {
+            Operand::Value(v) => v,
+            Operand::Old => item,
+        };
+        let worry_level = self.operator.exec(item, operand) / worry_reduction_rate;
+        let target_monkey = if worry_level % self.test_divisor == 0 {
+            self.targets.0
+        } else {
+            self.targets.1
+        };
+        (target_monkey, worry_level)
     }
 }
 
@@ -117,78 +335,83 @@ impl Operator {
 }
 
 impl FromStr for Monkey {
-    type Err = Infallible;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.trim().lines().map(|l| l.trim());
-        let items = parse_starting_items(lines.next().unwrap());
-        let (operator, operand) = parse_operation(lines.next().unwrap());
-        let test_divisor = parse_test(lines.next().unwrap());
-        let targets = parse_targets(lines);
+        let mut next_line = |name: &str| {
+            lines
+                .next()
+                .ok_or_else(|| format!("missing {name} declaration"))
+        };
+        let items = parse_starting_items(next_line("starting items")?)?;
+        let (operator, operand) = parse_operation(next_line("operation")?)?;
+        let test_divisor = parse_test(next_line("test")?)?;
+        let if_true = parse_target(next_line("target")?, "If true: throw to monkey ")?;
+        let if_false = parse_target(next_line("target")?, "If false: throw to monkey ")?;
         Ok(Self {
             items,
             operator,
             operand,
             test_divisor,
-            targets,
+            targets: (if_true, if_false),
             inspected: 0,
         })
     }
 }
 
-fn parse_targets<'a>(mut lines: impl Iterator<Item = &'a str>) -> (usize, usize) {
-    let if_true = lines
-        .next()
-        .unwrap()
-        .strip_prefix("If true: throw to monkey ")
-        .unwrap()
-        .parse::<usize>()
-        .unwrap();
-    let if_false = lines
-        .next()
-        .unwrap()
-        .strip_prefix("If false: throw to monkey ")
-        .unwrap()
-        .parse::<usize>()
-        .unwrap();
-    (if_true, if_false)
+fn parse_target(declaration: &str, prefix: &str) -> Result<usize, String> {
+    declaration
+        .strip_prefix(prefix)
+        .and_then(|t| t.parse::<usize>().ok())
+        .ok_or_else(|| format!("invalid target: {declaration}"))
 }
 
-fn parse_test(declaration: &str) -> WorryLevel {
+fn parse_test(declaration: &str) -> Result<WorryLevel, String> {
     declaration
         .strip_prefix("Test: divisible by ")
-        .unwrap()
-        .parse::<WorryLevel>()
-        .unwrap()
+        .and_then(|d| d.parse::<WorryLevel>().ok())
+        .filter(|&d| d != 0)
+        .ok_or_else(|| format!("invalid test: {declaration}"))
 }
 
-fn parse_starting_items(declaration: &str) -> Vec<WorryLevel> {
-    declaration
-        .strip_prefix("Starting items: ")
-        .unwrap()
+fn parse_starting_items(declaration: &str) -> Result<Vec<WorryLevel>, String> {
+    let items = declaration
+        .strip_prefix("Starting items:")
+        .ok_or_else(|| format!("invalid starting items: {declaration}"))?
+        .trim();
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+    items
         .split(", ")
-        .map(|i| i.parse::<WorryLevel>().unwrap())
+        .map(|i| {
+            i.parse::<WorryLevel>()
+                .map_err(|_| format!("invalid item: {i}"))
+        })
         .collect()
 }
 
-fn parse_operation(declaration: &str) -> (Operator, Operand) {
+fn parse_operation(declaration: &str) -> Result<(Operator, Operand), String> {
+    let invalid = || format!("invalid operation: {declaration}");
     let (operator, operand) = declaration
         .strip_prefix("Operation: new = old ")
-        .unwrap()
-        .split_once(' ')
-        .unwrap();
+        .and_then(|o| o.split_once(' '))
+        .ok_or_else(invalid)?;
     let operator = match operator {
         "*" => Operator::Multiply,
         "/" => Operator::Divide,
         "+" => Operator::Add,
-        _ => panic!("Unexpected operator: {operator}"),
+        _ => return Err(invalid()),
     };
-    let operand = if operand == "old" {
-        Operand::Old
-    } else {
-        Operand::Value(operand.parse::<WorryLevel>().unwrap())
+    let operand = match operand {
+        "old" => Operand::Old,
+        value => Operand::Value(value.parse::<WorryLevel>().map_err(|_| invalid())?),
     };
-    (operator, operand)
+    if matches!((operator, operand), (Operator::Divide, Operand::Value(0))) {
+        return Err(invalid());
+    }
+    Ok((operator, operand))
 }
 
 #[cfg(test)]
@@ -241,6 +464,181 @@ Monkey 3:
         assert_eq!(part_2(input.trim()), expected);
     }
 
+    #[test]
+    fn should_count_inspections_per_round() {
+        let mut troop: Troop = EXAMPLE.trim().parse().unwrap();
+        assert_eq!(troop.play_round(3), vec![2, 4, 3, 5]);
+        // The last monkey throws everything to monkeys that already played
+        assert!(troop.items().last().unwrap().is_empty());
+        for _ in 1..20 {
+            troop.play_round(3);
+        }
+        assert_eq!(
+            troop.inspections().collect::<Vec<_>>(),
+            vec![101, 95, 7, 105]
+        );
+    }
+
+    #[rstest]
+    #[case::example(EXAMPLE)]
+    #[case::input(INPUT)]
+    fn inspections_after_should_match_simulation(#[case] input: &str) {
+        let mut troop: Troop = input.trim().parse().unwrap();
+        let extrapolated = troop.inspections_after(10_000);
+        for _ in 0..10_000 {
+            troop.play_round(1);
+        }
+        let simulated: Vec<_> = troop.inspections().map(|i| i as u64).collect();
+        assert_eq!(extrapolated, simulated);
+    }
+
+    #[test]
+    fn worry_levels_should_be_reduced_modulo_lcm_of_divisors() {
+        let input = EXAMPLE
+            .trim()
+            .replace("divisible by 23", "divisible by 4")
+            .replace("divisible by 19", "divisible by 6");
+        let troop: Troop = input.parse().unwrap();
+        assert_eq!(troop.max_worry_level, 4 * 3 * 13 * 17);
+    }
+
+    #[test]
+    fn should_compute_monkey_business_for_astronomically_many_rounds() {
+        let troop: Troop = EXAMPLE.trim().parse().unwrap();
+        let inspections = troop.inspections_after(1_000_000_000_000);
+        assert_eq!(
+            inspections,
+            vec![
+                5_217_653_508_757,
+                4_782_346_491_239,
+                193_256_578_955,
+                5_202_028_508_760
+            ]
+        );
+        assert_eq!(
+            solve_extrapolated(EXAMPLE.trim(), 1_000_000_000_000),
+            27_142_382_301_385_558_311_211_320
+        );
+    }
+
+    #[test]
+    fn inspections_after_should_include_rounds_already_played() {
+        let mut troop: Troop = EXAMPLE.trim().parse().unwrap();
+        let expected = troop.inspections_after(10_000);
+        for _ in 0..20 {
+            troop.play_round(1);
+        }
+        assert_eq!(troop.inspections_after(9_980), expected);
+    }
+
+    #[test]
+    fn should_report_items_as_the_puzzle_does() {
+        let mut troop: Troop = EXAMPLE.trim().parse().unwrap();
+        let mut reporter = Reporter::new(Vec::new(), Report::Items);
+        troop.play_observed_round(3, &mut reporter);
+        troop.play_observed_round(3, &mut reporter);
+        let expected = "\
+After round 1, the monkeys are holding items with these worry levels:
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2: 
+Monkey 3: 
+
+After round 2, the monkeys are holding items with these worry levels:
+Monkey 0: 695, 10, 71, 135, 350
+Monkey 1: 43, 49, 58, 55, 362
+Monkey 2: 
+Monkey 3: 
+
+";
+        assert_eq!(
+            String::from_utf8(reporter.finish().unwrap()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn should_report_inspections_as_the_puzzle_does() {
+        let mut troop: Troop = EXAMPLE.trim().parse().unwrap();
+        let mut reporter = Reporter::new(Vec::new(), Report::Inspections);
+        troop.play_observed_round(1, &mut reporter);
+        let expected = "\
+== After round 1 ==
+Monkey 0 inspected items 2 times.
+Monkey 1 inspected items 4 times.
+Monkey 2 inspected items 3 times.
+Monkey 3 inspected items 6 times.
+
+";
+        assert_eq!(
+            String::from_utf8(reporter.finish().unwrap()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn should_notify_every_throw() {
+        #[derive(Default)]
+        struct Throws(Vec<(u32, usize, usize, WorryLevel)>);
+        impl Observer for Throws {
+            fn on_throw(&mut self, round: u32, from: usize, to: usize, worry_level: WorryLevel) {
+                self.0.push((round, from, to, worry_level));
+            }
+        }
+        let mut troop: Troop = EXAMPLE.trim().parse().unwrap();
+        let mut throws = Throws::default();
+        troop.play_observed_round(3, &mut throws);
+        assert_eq!(&throws.0[..2], &[(1, 0, 3, 500), (1, 0, 3, 620)]);
+        assert_eq!(throws.0.len(), 14);
+
+        // Without relief, the observer sees the worry levels before they are reduced
+        let mut troop: Troop = EXAMPLE.trim().parse().unwrap();
+        let mut throws = Throws::default();
+        for _ in 0..20 {
+            troop.play_observed_round(1, &mut throws);
+        }
+        assert!(throws.0.iter().any(|t| t.3 >= troop.max_worry_level));
+        assert!(troop.items().flatten().all(|i| *i < troop.max_worry_level));
+    }
+
+    #[test]
+    fn reporter_should_stop_at_first_write_error() {
+        struct Broken(usize);
+        impl io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                self.0 += 1;
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut troop: Troop = EXAMPLE.trim().parse().unwrap();
+        let mut reporter = Reporter::new(Broken(0), Report::Inspections);
+        for _ in 0..3 {
+            troop.play_observed_round(1, &mut reporter);
+        }
+        assert_eq!(reporter.out.0, 1);
+        let error = reporter.finish().err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[rstest]
+    #[case::missing_monkey(EXAMPLE.trim().replace("Monkey 3:", "Monkey 4:"))]
+    #[case::duplicated_monkey(EXAMPLE.trim().replace("Monkey 3:", "Monkey 2:"))]
+    #[case::unknown_target(EXAMPLE.trim().replace("throw to monkey 3", "throw to monkey 7"))]
+    #[case::throw_to_itself(EXAMPLE.trim().replace("If true: throw to monkey 2", "If true: throw to monkey 0"))]
+    #[case::invalid_header(EXAMPLE.trim().replace("Monkey 1:", "Monkey one:"))]
+    #[case::invalid_test(EXAMPLE.trim().replace("divisible by 19", "divisible by nineteen"))]
+    #[case::zero_divisor(EXAMPLE.trim().replace("divisible by 19", "divisible by 0"))]
+    #[case::invalid_operator(EXAMPLE.trim().replace("old + 6", "old % 6"))]
+    #[case::invalid_operand(EXAMPLE.trim().replace("old + 6", "old + six"))]
+    #[case::invalid_item(EXAMPLE.trim().replace("54, 65", "54, sixty"))]
+    #[case::missing_target(EXAMPLE.trim().replace("    If false: throw to monkey 0\n", ""))]
+    fn should_reject_invalid_troop(#[case] input: String) {
+        assert!(input.parse::<Troop>().is_err());
+    }
+
     #[rstest]
     #[case(r#"
     Starting items: 2
@@ -319,9 +717,8 @@ Monkey 3:
         If true: throw to monkey 1
         If false: throw to monkey 3
     "#, vec![(1,2080), (3, 1200), (3, 3136)])]
-    fn should_throw(#[case] mut monkey: Monkey, #[case] expected_throws: Vec<(usize, WorryLevel)>) {
-        let actual_throws: Vec<_> = monkey.throw_all(3).collect();
+    fn should_throw(#[case] monkey: Monkey, #[case] expected_throws: Vec<(usize, WorryLevel)>) {
+        let actual_throws: Vec<_> = monkey.items.iter().map(|i| monkey.inspect(*i, 3)).collect();
         assert_eq!(actual_throws, expected_throws);
-        assert_eq!(monkey.throw_all(3).count(), 0); // <- And there is nothing left to throw
     }
 }
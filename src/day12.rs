@@ -1,68 +1,267 @@
-use std::{convert::Infallible, str::FromStr};
+use std::{collections::HashSet, convert::Infallible, str::FromStr};
+
+use crate::{
+    netpbm::{ColorMap, Image},
+    search::{self, Search},
+};
 
 type Output = u64;
 
 pub fn part_1(input: &str) -> Output {
-    input.parse().unwrap()
+    let map: Map = input.parse().unwrap();
+    (map.route().unwrap().len() - 1) as Output
 }
 
 pub fn part_2(input: &str) -> Output {
-    input.parse().unwrap()
+    let map: Map = input.parse().unwrap();
+    map.routes_from_lowest()
+        .map(|(_, route)| (route.len() - 1) as Output)
+        .min()
+        .unwrap()
 }
 
-fn shortest_path_length(map: &str, start_level: char, end_level: char) -> u32 {
+#[cfg(test)]
+fn shortest_path_length(map: &str, start_level: char, end_level: char) -> u64 {
     let mut map = Map::parse(map);
-    let mut current_pos = map.find_and_replace('S', start_level);
-    let target_pos = map.find_and_replace('E', end_level);
-    let mut steps = 0;
-    while current_pos != target_pos {
-        current_pos = map
-            .directions_from(current_pos)
-            .map(|p| (p, map.get(p)))
-            .max_by_key(|(pos, v)| if *pos == target_pos { char::MAX } else { *v })
-            .unwrap()
-            .0;
-        steps += 1;
-    }
-    steps
+    let start = map.find_and_replace('S', start_level);
+    let end = map.find_and_replace('E', end_level);
+    map.routes_to(end).distance(start).unwrap()
+}
+
+type Position = (usize, usize);
+
+/// How many levels can be climbed up or down in a single step
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ClimbRule {
+    pub max_ascent: u32,
+    pub max_descent: u32,
+}
+
+impl Default for ClimbRule {
+    /// At most one level up, and any level down
+    fn default() -> Self {
+        Self {
+            max_ascent: 1,
+            max_descent: u32::MAX,
+        }
+    }
+}
+
+impl ClimbRule {
+    fn allows(&self, from: char, to: char) -> bool {
+        let change = to as i64 - from as i64;
+        change <= i64::from(self.max_ascent) && -change <= i64::from(self.max_descent)
+    }
+}
+
+/// Cost of a single step
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CostModel {
+    /// Cost of any step
+    pub base: u32,
+    /// Additional cost per level climbed up
+    pub per_ascent: u32,
+    /// Additional cost per level climbed down
+    pub per_descent: u32,
+}
+
+impl Default for CostModel {
+    /// Every step costs 1
+    fn default() -> Self {
+        Self {
+            base: 1,
+            per_ascent: 0,
+            per_descent: 0,
+        }
+    }
+}
+
+impl CostModel {
+    /// Computed in `u64`, where it cannot overflow even with the largest costs and elevation differences
+    fn cost(&self, from: char, to: char) -> u64 {
+        let (from, to) = (u64::from(from), u64::from(to));
+        u64::from(self.base)
+            + u64::from(self.per_ascent) * to.saturating_sub(from)
+            + u64::from(self.per_descent) * from.saturating_sub(to)
+    }
 }
 
 #[derive(Debug)]
-struct Map {
+pub struct Map {
     cells: Vec<Vec<char>>,
+    rule: ClimbRule,
 }
 
 impl Map {
     fn parse(map: &str) -> Self {
         Self {
             cells: map.lines().map(|l| l.chars().collect()).collect(),
+            rule: ClimbRule::default(),
         }
     }
 
-    fn get(&self, (x, y): (usize, usize)) -> char {
-        self.cells[y][x]
+    pub fn with_climb_rule(mut self, rule: ClimbRule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    fn width(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len()
     }
 
-    fn find_and_replace(&mut self, search: char, replace: char) -> (usize, usize) {
-        let (x, y) = self
-            .cells
+    /// Elevation of the cell, where `S` is at elevation `a` and `E` at elevation `z`
+    fn elevation(&self, (x, y): Position) -> char {
+        match self.cells[y][x] {
+            'S' => 'a',
+            'E' => 'z',
+            c => c,
+        }
+    }
+
+    fn find(&self, search: char) -> Option<Position> {
+        self.cells
             .iter()
             .enumerate()
-            .find_map(|(y, row)| Some((row.iter().enumerate().find(|(_, c)| **c == search)?.0, y)))
-            .unwrap();
+            .find_map(|(y, row)| Some((row.iter().position(|c| *c == search)?, y)))
+    }
+
+    #[cfg(test)]
+    fn find_and_replace(&mut self, search: char, replace: char) -> Position {
+        let (x, y) = self.find(search).unwrap();
         self.cells[y][x] = replace;
         (x, y)
     }
 
-    fn directions_from(&self, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
-        let width = self.cells[0].len();
-        let height = self.cells.len();
-        let value = self.cells[y][x] as u16;
+    fn neighbours(&self, (x, y): Position) -> impl Iterator<Item = Position> {
+        let (width, height) = (self.width(), self.height());
         [(-1, 0), (1, 0), (0, 1), (0, -1)]
             .into_iter()
             .map(move |(dx, dy)| ((x as i32 + dx) as usize, (y as i32 + dy) as usize))
             .filter(move |(x, y)| *x < width && *y < height)
-            .filter(move |(x, y)| self.cells[*y][*x] as u16 - 1 <= value)
+    }
+
+    fn can_climb(&self, from: Position, to: Position) -> bool {
+        self.rule.allows(self.elevation(from), self.elevation(to))
+    }
+
+    /// Cells that can be reached in one step from `from`
+    pub fn directions_from(&self, from: Position) -> impl Iterator<Item = Position> + '_ {
+        self.neighbours(from)
+            .filter(move |to| self.can_climb(from, *to))
+    }
+
+    /// Shortest routes from every cell to the `end`, explored backward from the `end`
+    pub fn routes_to(&self, end: Position) -> Routes {
+        let climbs_to = |to: &Position| {
+            let to = *to;
+            self.neighbours(to)
+                .filter(move |from| self.can_climb(*from, to))
+                .map(|from| (from, 1))
+        };
+        Routes(search::bfs(&climbs_to, [end], |_| false))
+    }
+
+    /// Cheapest path from `start` to `end` (both included) according to the `costs`, and its total cost
+    ///
+    /// Uses A* with the Manhattan distance, multiplied by the base cost of a step, as heuristic.
+    pub fn cheapest_path(
+        &self,
+        start: Position,
+        end: Position,
+        costs: &CostModel,
+    ) -> Option<(u64, Vec<Position>)> {
+        let steps = |from: &Position| {
+            let from = *from;
+            self.directions_from(from)
+                .map(move |to| (to, costs.cost(self.elevation(from), self.elevation(to))))
+        };
+        let heuristic = |&(x, y): &Position| {
+            (x.abs_diff(end.0) + y.abs_diff(end.1)) as u64 * u64::from(costs.base)
+        };
+        let search = search::astar(&steps, [start], |p| *p == end, heuristic);
+        Some((search.goal_cost()?, search.goal_path()?))
+    }
+
+    /// Cheapest path from `S` to `E` according to the `costs`, and its total cost
+    pub fn cheapest_route(&self, costs: &CostModel) -> Option<(u64, Vec<Position>)> {
+        self.cheapest_path(self.find('S')?, self.find('E')?, costs)
+    }
+
+    /// Shortest route from `S` to `E`, including both
+    pub fn route(&self) -> Option<Vec<Position>> {
+        self.routes_to(self.find('E')?).route_from(self.find('S')?)
+    }
+
+    /// Shortest route to `E` from every cell at elevation `a` that can reach it
+    pub fn routes_from_lowest(&self) -> impl Iterator<Item = (Position, Vec<Position>)> + '_ {
+        let routes = self.find('E').map(|end| self.routes_to(end));
+        (0..self.height())
+            .flat_map(move |y| (0..self.width()).map(move |x| (x, y)))
+            .filter(move |p| self.elevation(*p) == 'a')
+            .filter_map(move |start| Some((start, routes.as_ref()?.route_from(start)?)))
+    }
+
+    /// Renders the `route` with arrows pointing to the next cell, as in the puzzle diagrams
+    ///
+    /// The last cell of the route is marked with `E`, and cells outside of the route with `.`.
+    pub fn render_route(&self, route: &[Position]) -> String {
+        let mut rows = vec![vec!['.'; self.width()]; self.height()];
+        for step in route.windows(2) {
+            let ((x, y), (nx, ny)) = (step[0], step[1]);
+            rows[y][x] = match (nx as i64 - x as i64, ny as i64 - y as i64) {
+                (1, _) => '>',
+                (-1, _) => '<',
+                (_, 1) => 'v',
+                _ => '^',
+            };
+        }
+        if let Some(&(x, y)) = route.last() {
+            rows[y][x] = 'E';
+        }
+        rows.into_iter().fold(String::new(), |mut text, row| {
+            text.extend(row);
+            text.push('\n');
+            text
+        })
+    }
+
+    /// Image with one pixel per cell, coloured after its elevation (from `'a'` to `'z'`),
+    /// except for the cells of the `path` which are drawn with `path_color`
+    pub fn image<C: ColorMap<char>>(
+        &self,
+        path: &[Position],
+        colors: &C,
+        path_color: C::Pixel,
+    ) -> Image<C::Pixel> {
+        let path: HashSet<Position> = path.iter().copied().collect();
+        Image::from_fn(self.width(), self.height(), |position| {
+            if path.contains(&position) {
+                path_color
+            } else {
+                colors.color(self.elevation(position))
+            }
+        })
+    }
+}
+
+/// Shortest routes from every cell to a common end
+pub struct Routes(Search<Position>);
+
+impl Routes {
+    /// Number of steps from `start` to the end, if reachable
+    pub fn distance(&self, start: Position) -> Option<u64> {
+        self.0.cost(&start)
+    }
+
+    /// Cells of the shortest route from `start` to the end, both included
+    pub fn route_from(&self, start: Position) -> Option<Vec<Position>> {
+        let mut route = self.0.path_to(&start)?;
+        route.reverse();
+        Some(route)
     }
 }
 
@@ -78,6 +277,8 @@ impl FromStr for Map {
 mod tests {
     use std::collections::HashSet;
 
+    use crate::netpbm::{Gray, Grayscale};
+
     use super::*;
 
     const EXAMPLE: &str = r#"
@@ -91,19 +292,15 @@ abdefghi
     const INPUT: &str = include_str!("day12/input.txt");
 
     #[rstest]
-    #[ignore = "not implemented"]
     #[case::example(EXAMPLE, 31)]
-    #[ignore = "not implemented"]
-    #[case::input(INPUT, 0)]
+    #[case::input(INPUT, 437)]
     fn test_part_1(#[case] input: &str, #[case] expected: Output) {
         assert_eq!(part_1(input.trim()), expected);
     }
 
     #[rstest]
-    #[ignore = "not implemented"]
-    #[case::example(EXAMPLE, 0)]
-    #[ignore = "not implemented"]
-    #[case::input(INPUT, 0)]
+    #[case::example(EXAMPLE, 29)]
+    #[case::input(INPUT, 430)]
     fn test_part_2(#[case] input: &str, #[case] expected: Output) {
         assert_eq!(part_2(input.trim()), expected);
     }
@@ -114,16 +311,126 @@ abdefghi
     #[case("aSE", 'a', 'a', 1)]
     #[case("ES", 'a', 'a', 1)]
     #[case("SE\nbc", 'a', 'd', 3)]
-    // #[case("SbE\nbbb", 'a', 'c', 2)]
+    #[case("SbE\nbbb", 'a', 'c', 2)]
     fn find_shortest_path(
         #[case] map: &str,
         #[case] start_level: char,
         #[case] end_level: char,
-        #[case] expected: u32,
+        #[case] expected: u64,
     ) {
         assert_eq!(shortest_path_length(map, start_level, end_level), expected);
     }
 
+    #[test]
+    fn should_render_shortest_route() {
+        let map: Map = EXAMPLE.trim().parse().unwrap();
+        let route = map.route().unwrap();
+        assert_eq!(route.first(), Some(&(0, 0)));
+        assert_eq!(route.last(), Some(&(5, 2)));
+        assert_eq!(route.len(), 32);
+        assert!(route
+            .windows(2)
+            .all(|s| map.directions_from(s[0]).any(|p| p == s[1])));
+        assert_eq!(
+            map.render_route(&route),
+            "v..v<<<<\n>v.vv<<^\n.v.v>E^^\n.>v>>>^^\n..>>>>>^\n"
+        );
+    }
+
+    #[test]
+    fn should_find_route_from_every_lowest_cell() {
+        let map: Map = EXAMPLE.trim().parse().unwrap();
+        let routes: Vec<_> = map.routes_from_lowest().collect();
+        assert_eq!(routes.len(), 6);
+        assert!(routes
+            .iter()
+            .all(|(start, route)| route.first() == Some(start) && route.last() == Some(&(5, 2))));
+        let (best_start, best) = routes.iter().min_by_key(|(_, r)| r.len()).unwrap();
+        assert_eq!(*best_start, (0, 4));
+        assert_eq!(best.len(), 30);
+    }
+
+    #[rstest]
+    #[case::example(EXAMPLE, 31)]
+    #[case::input(INPUT, 437)]
+    fn cheapest_route_with_unit_costs_should_be_shortest(
+        #[case] input: &str,
+        #[case] expected: u64,
+    ) {
+        let map: Map = input.trim().parse().unwrap();
+        let (cost, path) = map.cheapest_route(&CostModel::default()).unwrap();
+        assert_eq!(cost, expected);
+        assert_eq!(path.len() as u64, expected + 1);
+        assert!(path
+            .windows(2)
+            .all(|s| map.directions_from(s[0]).any(|p| p == s[1])));
+    }
+
+    #[rstest]
+    #[case(1, 0, 0, 4)]
+    #[case(1, 1, 1, 6)]
+    #[case(1, 2, 0, 6)]
+    #[case(3, 1, 1, 16)]
+    #[case(1, u32::MAX, u32::MAX, 6)]
+    #[case(u32::MAX, 0, 0, 4 * u32::MAX as u64)]
+    fn should_find_cheapest_path(
+        #[case] base: u32,
+        #[case] per_ascent: u32,
+        #[case] per_descent: u32,
+        #[case] expected: u64,
+    ) {
+        let map: Map = "abcba\naaaaa".parse().unwrap();
+        let costs = CostModel {
+            base,
+            per_ascent,
+            per_descent,
+        };
+        let (cost, path) = map.cheapest_path((0, 0), (4, 0), &costs).unwrap();
+        assert_eq!(cost, expected);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(4, 0)));
+    }
+
+    #[rstest]
+    #[case("ac", 1, u32::MAX, (0, 0), &[])]
+    #[case("ac", 2, u32::MAX, (0, 0), &[(1, 0)])]
+    #[case("aba", 1, 0, (1, 0), &[])]
+    #[case("aca", 1, 1, (1, 0), &[])]
+    #[case("acb", 1, 1, (1, 0), &[(2, 0)])]
+    #[case("\0\0\u{1}", 0, 0, (0, 0), &[(1, 0)])]
+    #[case("\0\u{1}\0", 1, 0, (0, 0), &[(1, 0)])]
+    fn should_apply_climb_rule(
+        #[case] map: &str,
+        #[case] max_ascent: u32,
+        #[case] max_descent: u32,
+        #[case] from: Position,
+        #[case] expected: &[Position],
+    ) {
+        let map = Map::parse(map).with_climb_rule(ClimbRule {
+            max_ascent,
+            max_descent,
+        });
+        let actual: HashSet<_> = map.directions_from(from).collect();
+        assert_eq!(actual, expected.iter().copied().collect());
+    }
+
+    #[test]
+    fn climb_rule_should_apply_to_routes() {
+        let map: Map = EXAMPLE.trim().parse().unwrap();
+        let map = map.with_climb_rule(ClimbRule {
+            max_ascent: 25,
+            max_descent: 25,
+        });
+        assert_eq!(map.route().unwrap().len(), 8);
+    }
+
+    #[test]
+    fn unreachable_end_should_have_no_route() {
+        let map: Map = "SazE".parse().unwrap();
+        assert_eq!(map.route(), None);
+        assert_eq!(map.routes_from_lowest().count(), 0);
+    }
+
     #[rstest]
     #[case("ab", (0, 0), &[(1, 0)])]
     #[case("ab", (1, 0), &[(0, 0)])]
@@ -142,4 +449,23 @@ abdefghi
         let actual: HashSet<_> = map.directions_from(from_pos).collect();
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn should_export_elevations_with_path() {
+        let map: Map = EXAMPLE.trim().parse().unwrap();
+        let path = [(0, 0), (1, 0), (1, 1)];
+        let image = map.image(
+            &path,
+            &Grayscale {
+                min: 'a'.into(),
+                max: 'z'.into(),
+            },
+            Gray(255),
+        );
+        assert_eq!((image.width(), image.height()), (8, 5));
+        assert_eq!(image.get((1, 1)), Some(Gray(255)));
+        assert_eq!(image.get((0, 1)), Some(Gray(0)));
+        assert_eq!(image.get((2, 0)), Some(Gray(10)));
+        assert_eq!(image.get((5, 2)), Some(Gray(255)));
+    }
 }
@@ -1,4 +1,15 @@
-use std::{fmt::Debug, ops::ControlFlow};
+use std::{
+    fmt::Debug,
+    iter,
+    ops::{ControlFlow, Range},
+    str::FromStr,
+};
+
+use crate::{
+    animation::{Frame, Viewport},
+    cycle,
+    netpbm::{ColorMap, Image},
+};
 
 type Output = usize;
 
@@ -9,44 +20,211 @@ pub fn part_1(input: &str) -> Output {
 }
 
 pub fn part_2(input: &str) -> Output {
-    input.parse().unwrap()
+    Simulation::new(input).extrapolated_height(1_000_000_000_000) as Output
 }
 
-struct Simulation {
+/// Rocks of the puzzle, in the order they fall
+const DEFAULT_SHAPES: &str = "\
+####
+
+.#.
+###
+.#.
+
+..#
+..#
+###
+
+#
+#
+#
+#
+
+##
+##";
+
+#[derive(Clone)]
+pub struct Simulation {
     stream: Vec<char>,
     stream_index: usize,
-
-    /// Row major (bottom to up)
-    cells: Vec<Vec<bool>>,
+    rock_index: usize,
+    /// Position of the bottom-left corner of the falling rock
+    falling: Option<(usize, usize)>,
+    chamber: Chamber,
+    tower: Tower,
 }
 
 impl Simulation {
-    fn new(stream: &str) -> Self {
+    pub fn new(stream: &str) -> Self {
+        Self::with_chamber(stream, Chamber::default())
+    }
+
+    pub fn with_chamber(stream: &str, chamber: Chamber) -> Self {
         Self {
             stream: stream.chars().collect(),
             stream_index: 0,
-            cells: Vec::new(),
+            rock_index: 0,
+            falling: None,
+            tower: Tower::new(chamber.width),
+            chamber,
         }
     }
 
-    fn simulate(&mut self, rock_count: u16) {
-        (0..(rock_count as usize))
-            .map(|i| SHAPES[i % SHAPES.len()])
-            .for_each(|shape| self.drop_shape(shape));
+    pub fn simulate(&mut self, rock_count: u64) {
+        (0..rock_count).for_each(|_| self.drop_rock());
     }
 
-    fn drop_shape(&mut self, shape: Shape) {
-        let mut position = (2, self.height() + 3);
-        while let ControlFlow::Continue(_) = self.step_shape(&mut position, shape) {}
-        self.save_end_pos(position, shape);
+    fn drop_rock(&mut self) {
+        while !self.step() {}
     }
 
-    fn step_shape(&mut self, (x, y): &mut (usize, usize), shape: Shape) -> ControlFlow<()> {
-        let d = self.stream[self.stream_index % self.stream.len()];
+    /// Pushes the falling rock with the next jet and lets it fall by one unit,
+    /// spawning a new rock first if none is falling
+    ///
+    /// Returns true when the rock comes to rest.
+    pub fn step(&mut self) -> bool {
+        let shapes = &self.chamber.shapes;
+        let shape = &shapes[self.rock_index % shapes.len()];
+        let (dx, dy) = self.chamber.spawn_offset;
+        let spawn = (dx, self.tower.height() + dy);
+        let position = self.falling.get_or_insert(spawn);
+        let jet = self.stream[self.stream_index % self.stream.len()];
         self.stream_index += 1;
-        if d == '>' && self.can_go_to((*x + 1, *y), shape) {
+        if let ControlFlow::Break(_) = self.tower.step_shape(position, jet, shape) {
+            self.tower.save_end_pos(*position, shape);
+            self.falling = None;
+            self.rock_index += 1;
+            return true;
+        }
+        false
+    }
+
+    /// Renders the chamber in the `viewport`, with the falling rock drawn as `@`
+    ///
+    /// The chamber spans from `x = 0` to `x = width - 1`, with walls on each side and the floor at `y = -1`.
+    pub fn frame(&self, viewport: &Viewport) -> Frame {
+        let width = self.chamber.width as i64;
+        let falling = self
+            .falling
+            .map(|position| (position, self.falling_shape()));
+        viewport.render(|(x, y)| {
+            let wall = x == -1 || x == width;
+            match (x, y) {
+                _ if x < -1 || x > width || y < -1 => ' ',
+                (_, -1) if wall => '+',
+                (_, -1) => '-',
+                _ if wall => '|',
+                _ => {
+                    let (x, y) = (x as usize, y as usize);
+                    let is_falling = falling.map_or(false, |((px, py), shape)| {
+                        x >= px && y >= py && shape.blocks().contains(&(x - px, y - py))
+                    });
+                    if is_falling {
+                        '@'
+                    } else {
+                        self.tower.cell((x, y))
+                    }
+                }
+            }
+        })
+    }
+
+    /// Renders every step of the fall of `rock_count` rocks, in a viewport of the given `height`
+    /// scrolling up with the falling rocks
+    pub fn frames(mut self, rock_count: u64, height: usize) -> impl Iterator<Item = Frame> {
+        let width = self.chamber.width;
+        let mut viewport = Viewport::new(width + 2, height)
+            .with_margin(height / 4)
+            .with_origin((-1, -1));
+        let mut settled = 0;
+        iter::from_fn(move || {
+            if settled == rock_count {
+                return None;
+            }
+            if self.step() {
+                settled += 1;
+            }
+            let top = match self.falling {
+                Some((_, y)) => y + self.falling_shape().height(),
+                None => self.height(),
+            };
+            viewport.follow(((width as i64 - 1) / 2, top as i64));
+            Some(self.frame(&viewport))
+        })
+    }
+
+    /// Image of the whole chamber, from the floor (or the discarded rows) up to the falling rock,
+    /// with one pixel per character of [`Self::frame`]
+    pub fn image<C: ColorMap<char>>(&self, colors: &C) -> Image<C::Pixel> {
+        let top = match self.falling {
+            Some((_, y)) => y + self.falling_shape().height(),
+            None => self.height(),
+        };
+        let bottom = self.tower.pruned as i64 - 1;
+        let viewport = Viewport::new(self.chamber.width + 2, (top as i64 - bottom) as usize)
+            .with_origin((-1, bottom));
+        Image::from_frame(&self.frame(&viewport), colors)
+    }
+
+    fn falling_shape(&self) -> &Shape {
+        &self.chamber.shapes[self.rock_index % self.chamber.shapes.len()]
+    }
+
+    pub fn height(&self) -> usize {
+        self.tower.height()
+    }
+
+    /// Height of the tower after `rock_count` rocks, extrapolated from the first time the chamber is back
+    /// in the same state (same next rock, same next jet, and same reachable rows)
+    ///
+    /// The simulation must not be in the middle of a rock fall,
+    /// and the jets must eventually seal the bottom of the tower, otherwise the state never repeats.
+    /// Panics if the height overflows `u64`.
+    pub fn extrapolated_height(self, rock_count: u64) -> u64 {
+        let run = cycle::detect_hashing(
+            self,
+            Self::drop_rock,
+            |s| {
+                (
+                    s.rock_index % s.chamber.shapes.len(),
+                    s.stream_index % s.stream.len(),
+                    s.tower.rows.clone(),
+                )
+            },
+            |s| s.height() as u64,
+        );
+        run.metric_at(rock_count).expect("tower height overflows")
+    }
+}
+
+/// Settled rocks, one bitmask per row (bit `x` is set when column `x` is blocked)
+///
+/// Rows that can no longer be reached by falling rocks are discarded.
+#[derive(Clone)]
+struct Tower {
+    width: usize,
+    /// Row major (bottom to up), starting after the discarded rows
+    rows: Vec<u8>,
+    /// Number of discarded rows
+    pruned: usize,
+    /// Cells of each row that a falling block can reach, as computed by [`Tower::prune`]
+    reachable: Vec<u8>,
+}
+
+impl Tower {
+    fn new(width: usize) -> Self {
+        Self {
+            width,
+            rows: Vec::new(),
+            pruned: 0,
+            reachable: Vec::new(),
+        }
+    }
+
+    fn step_shape(&self, (x, y): &mut (usize, usize), jet: char, shape: &Shape) -> ControlFlow<()> {
+        if jet == '>' && self.can_go_to((*x + 1, *y), shape) {
             *x += 1;
-        } else if d == '<' && *x > 0 && self.can_go_to((*x - 1, *y), shape) {
+        } else if jet == '<' && *x > 0 && self.can_go_to((*x - 1, *y), shape) {
             *x -= 1;
         }
         if *y > 0 && self.can_go_to((*x, *y - 1), shape) {
@@ -57,88 +235,242 @@ impl Simulation {
         ControlFlow::Continue(())
     }
 
-    fn save_end_pos(&mut self, (x, y): (usize, usize), shape: Shape) {
-        self.cells
-            .resize_with(self.cells.len().max(shape.height() + y), || vec![false; 7]);
-        shape.blocks().iter().copied().for_each(|(dx, dy)| {
-            self.cells[y + dy][x + dx] = true;
-        })
+    fn save_end_pos(&mut self, (x, y): (usize, usize), shape: &Shape) {
+        let bottom = y - self.pruned;
+        let top = bottom + shape.height();
+        if self.rows.len() < top {
+            self.rows.resize(top, 0);
+        }
+        for (row, mask) in self.rows[bottom..top].iter_mut().zip(shape.rows()) {
+            *row |= mask << x;
+        }
+        self.prune(bottom..top);
     }
 
-    fn can_go_to(&self, (x, y): (usize, usize), shape: Shape) -> bool {
-        !shape
-            .blocks()
-            .iter()
+    /// Discards the rows that no falling rock can reach anymore, after the `changed` rows received a rock
+    ///
+    /// Blocks of a rock only move left, right or down, so going down from the empty row on top of the tower,
+    /// a cell can be reached if it is free, and next to or under a reachable cell.
+    /// The reachability of a row only depends on the rows above it, so it is only recomputed from the top of
+    /// the changed rows, and until it matches the previously known one.
+    fn prune(&mut self, changed: Range<usize>) {
+        self.reachable.resize(self.rows.len(), 0);
+        let mut above = self
+            .reachable
+            .get(changed.end)
             .copied()
-            .map(|(dx, dy)| (x + dx, y + dy))
-            .any(|p| self.is_blocked(p))
+            .unwrap_or(self.free());
+        for y in (0..changed.end).rev() {
+            let reachable = self.reachable_in_row(y, above);
+            if y < changed.start && reachable == self.reachable[y] {
+                return;
+            }
+            self.reachable[y] = reachable;
+            if reachable == 0 {
+                self.rows.drain(..=y);
+                self.reachable.drain(..=y);
+                self.pruned += y + 1;
+                return;
+            }
+            above = reachable;
+        }
+    }
+
+    /// Cells of the row `y` reachable from the `above` cells of the row above it
+    fn reachable_in_row(&self, y: usize, above: u8) -> u8 {
+        let empty = self.free() & !self.rows[y];
+        let mut reachable = above & empty;
+        loop {
+            let spread = (reachable | reachable << 1 | reachable >> 1) & empty;
+            if spread == reachable {
+                return reachable;
+            }
+            reachable = spread;
+        }
+    }
+
+    /// Mask of the cells inside the chamber
+    fn free(&self) -> u8 {
+        u8::MAX >> (8 - self.width)
     }
 
-    fn is_blocked(&self, (x, y): (usize, usize)) -> bool {
-        match self.cells.get(y) {
-            Some(row) => row.get(x).copied().unwrap_or(true),
-            None => x >= 7,
+    fn can_go_to(&self, (x, y): (usize, usize), shape: &Shape) -> bool {
+        if x + shape.width() > self.width || y < self.pruned {
+            return false;
         }
+        let bottom = y - self.pruned;
+        shape
+            .rows()
+            .iter()
+            .zip(self.rows.iter().skip(bottom))
+            .all(|(mask, row)| row & (mask << x) == 0)
     }
 
     fn height(&self) -> usize {
-        self.cells.len()
+        self.pruned + self.rows.len()
+    }
+
+    /// `#` for rock, `.` for air, and `~` for discarded rows
+    fn cell(&self, (x, y): (usize, usize)) -> char {
+        match y.checked_sub(self.pruned) {
+            None => '~',
+            Some(y) if self.rows.get(y).map_or(false, |row| row & (1 << x) != 0) => '#',
+            Some(_) => '.',
+        }
     }
 }
 
 impl Debug for Simulation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.cells.iter().rev() {
+        for row in self.tower.rows.iter().rev() {
             write!(f, "|")?;
-            for col in row {
-                write!(f, "{}", if *col { '#' } else { '.' })?;
+            for x in 0..self.tower.width {
+                write!(f, "{}", if row & (1 << x) != 0 { '#' } else { '.' })?;
             }
             writeln!(f, "|")?;
         }
-        writeln!(f, "+-------+")?;
+        let floor = if self.tower.pruned == 0 { '-' } else { '~' };
+        writeln!(f, "+{}+", floor.to_string().repeat(self.tower.width))?;
         Ok(())
     }
 }
 
-const SHAPES: [Shape; 5] = [
-    Shape::HorizontalLine,
-    Shape::Plus,
-    Shape::Corner,
-    Shape::VerticalLine,
-    Shape::Square,
-];
-
-#[derive(Debug, Copy, Clone)]
-enum Shape {
-    HorizontalLine,
-    Plus,
-    Corner,
-    VerticalLine,
-    Square,
+/// Shape of the chamber, and of the rocks falling in it
+#[derive(Debug, Clone)]
+pub struct Chamber {
+    width: usize,
+    spawn_offset: (usize, usize),
+    shapes: Vec<Shape>,
 }
 
-impl Shape {
-    fn blocks(self) -> &'static [(usize, usize)] {
-        match self {
-            Shape::HorizontalLine => &[(0, 0), (1, 0), (2, 0), (3, 0)],
-            Shape::Plus => &[(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)],
-            Shape::Corner => &[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)],
-            Shape::VerticalLine => &[(0, 0), (0, 1), (0, 2), (0, 3)],
-            Shape::Square => &[(0, 0), (1, 0), (0, 1), (1, 1)],
+impl Chamber {
+    /// Rocks appear `spawn_offset.0` cells away from the left wall,
+    /// and `spawn_offset.1` cells above the highest rock (or the floor)
+    pub fn new(
+        width: usize,
+        spawn_offset: (usize, usize),
+        shapes: Vec<Shape>,
+    ) -> Result<Self, String> {
+        if !(1..=8).contains(&width) {
+            return Err(format!(
+                "chamber width must be between 1 and 8, got {width}"
+            ));
         }
+        if shapes.is_empty() {
+            return Err("a chamber needs at least one shape".into());
+        }
+        if let Some(shape) = shapes.iter().find(|s| spawn_offset.0 + s.width() > width) {
+            return Err(format!(
+                "shape of width {} does not fit at {} from the left wall of a {width} wide chamber",
+                shape.width(),
+                spawn_offset.0
+            ));
+        }
+        Ok(Self {
+            width,
+            spawn_offset,
+            shapes,
+        })
+    }
+
+    /// Parses shapes drawn with `#` (rock) and `.` (air), separated by blank lines
+    pub fn parse_shapes(art: &str) -> Result<Vec<Shape>, String> {
+        art.trim().split("\n\n").map(str::parse).collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn spawn_offset(&self) -> (usize, usize) {
+        self.spawn_offset
+    }
+
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
     }
-    fn height(self) -> usize {
-        match self {
-            Shape::HorizontalLine => 1,
-            Shape::Corner | Shape::Plus => 3,
-            Shape::Square => 2,
-            Shape::VerticalLine => 4,
+}
+
+impl Default for Chamber {
+    fn default() -> Self {
+        Self::new(7, (2, 3), Self::parse_shapes(DEFAULT_SHAPES).unwrap()).unwrap()
+    }
+}
+
+/// Rock shape
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Shape {
+    /// Offsets from the bottom-left corner
+    blocks: Vec<(usize, usize)>,
+    /// Bitmask of each row, from the bottom
+    rows: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+impl Shape {
+    pub fn blocks(&self) -> &[(usize, usize)] {
+        &self.blocks
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn rows(&self) -> &[u8] {
+        &self.rows
+    }
+}
+
+impl FromStr for Shape {
+    type Err = String;
+
+    /// Parses a shape drawn with `#` (rock) and `.` (air), top row first
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<&str> = s.trim().lines().map(str::trim).collect();
+        let mut blocks = Vec::new();
+        for (dy, row) in rows.iter().rev().enumerate() {
+            for (dx, c) in row.chars().enumerate() {
+                match c {
+                    '#' => blocks.push((dx, dy)),
+                    '.' => (),
+                    _ => return Err(format!("invalid character in rock shape: {c:?}")),
+                }
+            }
+        }
+        let min_x = blocks
+            .iter()
+            .map(|&(x, _)| x)
+            .min()
+            .ok_or("empty rock shape")?;
+        let min_y = blocks.iter().map(|&(_, y)| y).min().unwrap_or_default();
+        blocks
+            .iter_mut()
+            .for_each(|(x, y)| (*x, *y) = (*x - min_x, *y - min_y));
+        let width = blocks.iter().map(|&(x, _)| x + 1).max().unwrap_or_default();
+        if width > 8 {
+            return Err(format!("rock shapes can be at most 8 wide, got {width}"));
         }
+        let height = blocks.iter().map(|&(_, y)| y + 1).max().unwrap_or_default();
+        let mut rows = vec![0; height];
+        blocks.iter().for_each(|&(x, y)| rows[y] |= 1 << x);
+        Ok(Self {
+            blocks,
+            rows,
+            width,
+            height,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::netpbm::{Palette, Rgb};
+
     use super::*;
 
     const EXAMPLE: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
@@ -164,7 +496,7 @@ mod tests {
     #[case(EXAMPLE, 3, 6)]
     fn should_simulate_n_rocks(
         #[case] stream: &str,
-        #[case] rock_count: u16,
+        #[case] rock_count: u64,
         #[case] expected_height: usize,
     ) {
         let mut simulation = Simulation::new(stream);
@@ -174,10 +506,210 @@ mod tests {
     }
 
     #[rstest]
-    #[ignore = "not implemented"]
-    #[case::example(EXAMPLE, 0)]
-    #[ignore = "not implemented"]
-    #[case::input(INPUT, 0)]
+    #[case("####", 4, 1)]
+    #[case(".#.\n###\n.#.", 3, 3)]
+    #[case("..#\n..#\n###", 3, 3)]
+    #[case("#\n#\n#\n#", 1, 4)]
+    #[case("##\n##", 2, 2)]
+    #[case("...\n.#.\n...", 1, 1)]
+    #[case("  #.\n  ##", 2, 2)]
+    fn should_derive_shape_size(
+        #[case] art: &str,
+        #[case] expected_width: usize,
+        #[case] expected_height: usize,
+    ) {
+        let shape: Shape = art.parse().unwrap();
+        assert_eq!(shape.width(), expected_width);
+        assert_eq!(shape.height(), expected_height);
+    }
+
+    #[test]
+    fn default_chamber_should_have_puzzle_shapes() {
+        let chamber = Chamber::default();
+        let shapes: Vec<_> = chamber.shapes().iter().map(Shape::blocks).collect();
+        assert_eq!(chamber.width(), 7);
+        assert_eq!(chamber.spawn_offset(), (2, 3));
+        assert_eq!(
+            shapes,
+            vec![
+                &[(0, 0), (1, 0), (2, 0), (3, 0)][..],
+                &[(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)][..],
+                &[(0, 0), (1, 0), (2, 0), (2, 1), (2, 2)][..],
+                &[(0, 0), (0, 1), (0, 2), (0, 3)][..],
+                &[(0, 0), (1, 0), (0, 1), (1, 1)][..],
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(1, (0, 3), "#", ">", 10, 10)]
+    #[case(1, (0, 0), "#\n#", "<", 5, 10)]
+    #[case(2, (0, 1), "#\n\n##\n##", ">", 3, 4)]
+    #[case(3, (0, 3), "###", "<>", 4, 4)]
+    #[case(4, (1, 2), "#", ">", 5, 5)]
+    #[case(8, (0, 0), "####", ">", 2, 2)]
+    #[case(8, (4, 3), "####", "<<<<>>>>", 2, 1)]
+    fn should_simulate_custom_chamber(
+        #[case] width: usize,
+        #[case] spawn_offset: (usize, usize),
+        #[case] shapes: &str,
+        #[case] stream: &str,
+        #[case] rock_count: u64,
+        #[case] expected_height: usize,
+    ) {
+        let chamber =
+            Chamber::new(width, spawn_offset, Chamber::parse_shapes(shapes).unwrap()).unwrap();
+        let mut simulation = Simulation::with_chamber(stream, chamber);
+        simulation.simulate(rock_count);
+        assert_eq!(simulation.height(), expected_height);
+    }
+
+    #[test]
+    fn should_parse_default_shapes_as_row_masks() {
+        let chamber = Chamber::default();
+        let masks: Vec<&[u8]> = chamber.shapes().iter().map(Shape::rows).collect();
+        assert_eq!(
+            masks,
+            vec![
+                &[0b1111][..],
+                &[0b010, 0b111, 0b010],
+                &[0b111, 0b100, 0b100],
+                &[0b1, 0b1, 0b1, 0b1],
+                &[0b11, 0b11],
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(7, (2, 3), "")]
+    #[case(7, (2, 3), "#x#")]
+    #[case(7, (2, 3), "#\n\n...")]
+    #[case(3, (0, 3), "####")]
+    #[case(4, (1, 3), "####")]
+    #[case(9, (0, 3), "#")]
+    #[case(0, (0, 3), "#")]
+    #[case(9, (0, 3), "#########")]
+    fn should_reject_invalid_chamber(
+        #[case] width: usize,
+        #[case] spawn_offset: (usize, usize),
+        #[case] shapes: &str,
+    ) {
+        let result = Chamber::parse_shapes(shapes)
+            .and_then(|shapes| Chamber::new(width, spawn_offset, shapes));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn should_discard_unreachable_rows() {
+        let chamber = Chamber::new(2, (0, 1), Chamber::parse_shapes("#\n\n##").unwrap()).unwrap();
+        let mut simulation = Simulation::with_chamber("<", chamber);
+        simulation.simulate(3);
+        assert_eq!(simulation.height(), 3);
+        assert_eq!(simulation.tower.pruned, 2);
+        assert_eq!(simulation.tower.rows, vec![0b01]);
+    }
+
+    #[rstest]
+    #[case::example(EXAMPLE, 35, 53)]
+    #[case::input(INPUT, 1705, 2649)]
+    fn should_simulate_millions_of_rocks(
+        #[case] stream: &str,
+        #[case] cycle_rocks: u64,
+        #[case] cycle_height: usize,
+    ) {
+        let mut simulation = Simulation::new(stream.trim());
+        simulation.simulate(2_000_000);
+        let height = simulation.height();
+        simulation.simulate(cycle_rocks * 1000);
+        assert_eq!(simulation.height() - height, cycle_height * 1000);
+        assert!(simulation.tower.rows.len() < 100);
+    }
+
+    #[test]
+    fn should_render_falling_rock() {
+        let frames: Vec<_> = Simulation::new(EXAMPLE).frames(1, 8).collect();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(
+            frames[0].to_string(),
+            "\
+|.......|
+|.......|
+|.......|
+|.......|
+|...@@@@|
+|.......|
+|.......|
++-------+
+"
+        );
+        assert!(frames[3].to_string().ends_with("|..####.|\n+-------+\n"));
+    }
+
+    #[test]
+    fn should_export_tower_image() {
+        let palette = Palette::new(Rgb(255, 255, 255))
+            .with('#', Rgb(0, 0, 0))
+            .with('@', Rgb(255, 0, 0));
+        let mut simulation = Simulation::new(EXAMPLE);
+        simulation.simulate(2);
+        let image = simulation.image(&palette);
+        assert_eq!((image.width(), image.height()), (9, 5));
+        assert_eq!(image.get((3, 3)), Some(Rgb(0, 0, 0)));
+        assert_eq!(image.get((3, 2)), Some(Rgb(255, 255, 255)));
+        simulation.step();
+        let image = simulation.image(&palette);
+        assert_eq!(image.height(), 10);
+        assert_eq!(image.get((6, 0)), Some(Rgb(255, 0, 0)));
+    }
+
+    #[test]
+    fn frames_should_scroll_with_falling_rocks() {
+        let frames: Vec<_> = Simulation::new(EXAMPLE).frames(50, 20).collect();
+        assert!(frames.iter().all(|f| f.width() == 9 && f.height() == 20));
+        let settled = frames
+            .iter()
+            .filter(|f| !f.to_string().contains('@'))
+            .count();
+        assert_eq!(settled, 50);
+        assert!(!frames.last().unwrap().to_string().contains('+'));
+    }
+
+    #[rstest]
+    #[case::example(EXAMPLE)]
+    #[case::never_sealed("<")]
+    fn incremental_reachability_should_match_full_scan(#[case] stream: &str) {
+        let mut simulation = Simulation::new(stream);
+        for _ in 0..500 {
+            simulation.drop_rock();
+            let tower = &simulation.tower;
+            let mut above = tower.free();
+            let expected: Vec<u8> = (0..tower.rows.len())
+                .rev()
+                .map(|y| {
+                    above = tower.reachable_in_row(y, above);
+                    above
+                })
+                .collect();
+            assert!(tower.reachable.iter().rev().eq(expected.iter()));
+        }
+    }
+
+    #[rstest]
+    #[case::example(EXAMPLE, 100_000)]
+    #[case::input(INPUT, 100_000)]
+    fn extrapolation_should_match_direct_simulation(#[case] stream: &str, #[case] rock_count: u64) {
+        let simulation = Simulation::new(stream.trim());
+        let mut direct = simulation.clone();
+        direct.simulate(rock_count);
+        assert_eq!(
+            simulation.extrapolated_height(rock_count),
+            direct.height() as u64
+        );
+    }
+
+    #[rstest]
+    #[case::example(EXAMPLE, 1_514_285_714_288)]
+    #[case::input(INPUT, 1_553_665_689_155)]
     fn test_part_2(#[case] input: &str, #[case] expected: Output) {
         assert_eq!(part_2(input.trim()), expected);
     }
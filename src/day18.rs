@@ -1,10 +1,21 @@
-use std::collections::HashSet;
+use crate::voxel::{Mesh, VoxelGrid};
 
 type Output = u64;
 
 pub fn part_1(input: &str) -> Output {
-    let mut droplets = HashSet::<(i32, i32, i32)>::new();
-    let mut count = 0;
+    parse(input).surface_area() as Output
+}
+
+pub fn part_2(input: &str) -> Output {
+    input.parse().unwrap()
+}
+
+/// Surface of the droplet, with adjacent coplanar faces merged
+pub fn droplet_surface(input: &str) -> Mesh {
+    parse(input).surface()
+}
+
+fn parse(input: &str) -> VoxelGrid {
     input
         .lines()
         .map(|l| l.splitn(3, ',').collect::<Vec<_>>())
@@ -15,33 +26,7 @@ pub fn part_1(input: &str) -> Output {
                 v[2].parse().unwrap(),
             )
         })
-        .for_each(|pos| {
-            for side in sides(pos) {
-                if droplets.contains(&side) {
-                    count -= 1;
-                } else {
-                    count += 1;
-                }
-            }
-            droplets.insert(pos);
-        });
-    count
-}
-
-pub fn part_2(input: &str) -> Output {
-    input.parse().unwrap()
-}
-
-fn sides((x, y, z): (i32, i32, i32)) -> impl Iterator<Item = (i32, i32, i32)> {
-    [
-        (x - 1, y, z),
-        (x + 1, y, z),
-        (x, y - 1, z),
-        (x, y + 1, z),
-        (x, y, z - 1),
-        (x, y, z + 1),
-    ]
-    .into_iter()
+        .collect()
 }
 
 #[cfg(test)]
@@ -80,6 +65,15 @@ mod tests {
         assert_eq!(part_1(input.trim()), expected);
     }
 
+    #[rstest]
+    #[case::example(EXAMPLE)]
+    #[case::input(INPUT)]
+    fn surface_mesh_should_cover_surface_area(#[case] input: &str) {
+        let mesh = droplet_surface(input.trim());
+        assert_eq!(mesh.area(), part_1(input.trim()));
+        assert!(mesh.quads().len() as Output <= mesh.area());
+    }
+
     #[rstest]
     #[ignore = "not implemented"]
     #[case::example(EXAMPLE, 0)]
@@ -0,0 +1,317 @@
+use std::{fmt::Debug, iter, ops::RangeInclusive, vec};
+
+/// Integer type usable as a bound of an [`IntervalSet`]
+pub trait Bound: Copy + Ord + Debug {
+    fn checked_succ(self) -> Option<Self>;
+    fn checked_pred(self) -> Option<Self>;
+    /// Number of integers in `low..=high`
+    fn count(low: Self, high: Self) -> u128;
+}
+
+macro_rules! impl_bound {
+    ($($t:ty),*) => {
+        $(impl Bound for $t {
+            fn checked_succ(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+
+            fn checked_pred(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+
+            fn count(low: Self, high: Self) -> u128 {
+                (high as i128 - low as i128) as u128 + 1
+            }
+        })*
+    };
+}
+
+impl_bound!(i8, i16, i32, i64, u8, u16, u32, u64, usize, isize);
+
+/// Set of integers, stored as sorted, disjoint and non-adjacent inclusive intervals
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IntervalSet<T> {
+    intervals: Vec<(T, T)>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self {
+            intervals: Vec::new(),
+        }
+    }
+}
+
+impl<T: Bound> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every integer of the `range` to the set, merging it with the overlapping or adjacent intervals
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (low, high) = range.into_inner();
+        if low > high {
+            return;
+        }
+        // Intervals from `start` to `end` (excluded) overlap or touch the range
+        let start = self
+            .intervals
+            .partition_point(|&(_, h)| h.checked_succ().map_or(false, |next| next < low));
+        let end = self
+            .intervals
+            .partition_point(|&(l, _)| l <= high || high.checked_succ() == Some(l));
+        let merged = if start < end {
+            (
+                low.min(self.intervals[start].0),
+                high.max(self.intervals[end - 1].1),
+            )
+        } else {
+            (low, high)
+        };
+        self.intervals.splice(start..end, [merged]);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Number of integers in the set
+    ///
+    /// It is a `u128` so that the full range of `u64` or `i64` can be counted.
+    pub fn len(&self) -> u128 {
+        self.intervals.iter().map(|&(l, h)| T::count(l, h)).sum()
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.intervals.partition_point(|&(_, high)| high < value);
+        matches!(self.intervals.get(i), Some(&(low, _)) if low <= value)
+    }
+
+    /// Whether every integer of `other` is also in this set
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.difference(self).is_empty()
+    }
+
+    /// Smallest and biggest integers of the set
+    pub fn bounds(&self) -> Option<RangeInclusive<T>> {
+        Some(self.intervals.first()?.0..=self.intervals.last()?.1)
+    }
+
+    /// The disjoint intervals making up the set, in increasing order
+    pub fn intervals(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.intervals.iter().map(|&(low, high)| low..=high)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_unsorted(
+            self.intervals
+                .iter()
+                .chain(other.intervals.iter())
+                .copied()
+                .collect(),
+        )
+    }
+
+    /// Sorts the non-empty `intervals`, and merges the overlapping or adjacent ones in a single pass
+    fn from_unsorted(mut all: Vec<(T, T)>) -> Self {
+        all.sort_unstable();
+        let mut intervals: Vec<(T, T)> = Vec::with_capacity(all.len());
+        for (low, high) in all {
+            match intervals.last_mut() {
+                Some((_, last)) if last.checked_succ().map_or(true, |next| low <= next) => {
+                    *last = (*last).max(high);
+                }
+                _ => intervals.push((low, high)),
+            }
+        }
+        Self { intervals }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(&(a_low, a_high)), Some(&(b_low, b_high))) =
+            (self.intervals.get(i), other.intervals.get(j))
+        {
+            let (low, high) = (a_low.max(b_low), a_high.min(b_high));
+            if low <= high {
+                intervals.push((low, high));
+            }
+            if a_high < b_high {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { intervals }
+    }
+
+    /// Integers of this set that are not in `other`
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut intervals = Vec::new();
+        let mut removed = other.intervals.iter().copied().peekable();
+        for &(low, high) in &self.intervals {
+            // Start of the part of the interval that has not been removed yet
+            let mut rest = Some(low);
+            while let (Some(low), Some(&(r_low, r_high))) = (rest, removed.peek()) {
+                if r_high < low {
+                    removed.next();
+                    continue;
+                }
+                if r_low > high {
+                    break;
+                }
+                if r_low > low {
+                    intervals.push((low, r_low.checked_pred().unwrap()));
+                }
+                if r_high >= high {
+                    rest = None;
+                } else {
+                    rest = r_high.checked_succ();
+                    removed.next();
+                }
+            }
+            if let Some(low) = rest {
+                intervals.push((low, high));
+            }
+        }
+        Self { intervals }
+    }
+}
+
+impl<T: Bound> From<RangeInclusive<T>> for IntervalSet<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let (low, high) = range.into_inner();
+        Self {
+            intervals: if low <= high {
+                vec![(low, high)]
+            } else {
+                Vec::new()
+            },
+        }
+    }
+}
+
+impl<T: Bound> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        Self::from_unsorted(
+            iter.into_iter()
+                .map(RangeInclusive::into_inner)
+                .filter(|(low, high)| low <= high)
+                .collect(),
+        )
+    }
+}
+
+impl<T: Bound> IntoIterator for IntervalSet<T> {
+    type Item = RangeInclusive<T>;
+    type IntoIter = iter::Map<vec::IntoIter<(T, T)>, fn((T, T)) -> RangeInclusive<T>>;
+
+    /// The disjoint intervals making up the set, in increasing order
+    fn into_iter(self) -> Self::IntoIter {
+        self.intervals.into_iter().map(|(low, high)| low..=high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[RangeInclusive<i32>]) -> IntervalSet<i32> {
+        ranges.iter().cloned().collect()
+    }
+
+    #[rstest]
+    #[case(&[], &[])]
+    #[case(&[1..=3], &[1..=3])]
+    #[case(&[1..=3, 4..=5], &[1..=5])]
+    #[case(&[1..=3, 5..=6], &[1..=3, 5..=6])]
+    #[case(&[5..=6, 1..=3, 2..=4], &[1..=6])]
+    #[case(&[-5..=-2, 0..=0, -1..=-1], &[-5..=0])]
+    #[case(&[1..=10, 2..=3], &[1..=10])]
+    #[case(&[1..=2, 8..=9, 5..=5, 3..=7], &[1..=9])]
+    #[case(&[1..=2, 8..=9, 4..=6, 0..=0], &[0..=2, 4..=6, 8..=9])]
+    #[case(&[RangeInclusive::new(3, 1), 1..=1], &[1..=1])]
+    fn should_normalize_union(
+        #[case] ranges: &[RangeInclusive<i32>],
+        #[case] expected: &[RangeInclusive<i32>],
+    ) {
+        assert_eq!(set(ranges).intervals().collect::<Vec<_>>(), expected);
+        let mut inserted = IntervalSet::new();
+        ranges.iter().for_each(|r| inserted.insert(r.clone()));
+        assert_eq!(inserted.into_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[rstest]
+    #[case(&[1..=5], &[3..=8], &[3..=5])]
+    #[case(&[1..=5], &[6..=8], &[])]
+    #[case(&[1..=5, 10..=15], &[3..=12], &[3..=5, 10..=12])]
+    #[case(&[0..=100], &[1..=2, 50..=60, 99..=200], &[1..=2, 50..=60, 99..=100])]
+    fn should_compute_intersection(
+        #[case] a: &[RangeInclusive<i32>],
+        #[case] b: &[RangeInclusive<i32>],
+        #[case] expected: &[RangeInclusive<i32>],
+    ) {
+        assert_eq!(set(a).intersection(&set(b)), set(expected));
+        assert_eq!(set(b).intersection(&set(a)), set(expected));
+    }
+
+    #[rstest]
+    #[case(&[1..=5], &[3..=8], &[1..=2])]
+    #[case(&[1..=5], &[6..=8], &[1..=5])]
+    #[case(&[1..=5], &[1..=5], &[])]
+    #[case(&[1..=10], &[3..=4, 6..=6], &[1..=2, 5..=5, 7..=10])]
+    #[case(&[1..=5, 10..=15], &[3..=12], &[1..=2, 13..=15])]
+    #[case(&[1..=5, 10..=15], &[0..=0, 5..=5, 15..=20], &[1..=4, 10..=14])]
+    fn should_compute_difference(
+        #[case] a: &[RangeInclusive<i32>],
+        #[case] b: &[RangeInclusive<i32>],
+        #[case] expected: &[RangeInclusive<i32>],
+    ) {
+        assert_eq!(set(a).difference(&set(b)), set(expected));
+    }
+
+    #[test]
+    fn should_handle_extreme_bounds() {
+        let all: IntervalSet<u8> = (0..=u8::MAX).into();
+        let mut set = IntervalSet::from(0..=9);
+        set.insert(250..=255);
+        set.insert(255..=255);
+        assert_eq!(all.len(), 256);
+        assert_eq!(all.difference(&set), IntervalSet::from(10..=249));
+        assert_eq!(all.union(&set), all);
+        assert!(all.is_superset(&set));
+        assert!(!set.is_superset(&all));
+    }
+
+    #[test]
+    fn should_count_full_ranges() {
+        assert_eq!(IntervalSet::from(0..=u64::MAX).len(), 1 << 64);
+        assert_eq!(IntervalSet::from(i64::MIN..=i64::MAX).len(), 1 << 64);
+        assert_eq!(IntervalSet::from(i64::MIN..=i64::MIN).len(), 1);
+    }
+
+    #[rstest]
+    #[case(0, false)]
+    #[case(1, true)]
+    #[case(3, true)]
+    #[case(4, false)]
+    #[case(7, true)]
+    #[case(9, false)]
+    fn should_check_containment(#[case] value: i32, #[case] expected: bool) {
+        assert_eq!(set(&[1..=3, 5..=8]).contains(value), expected);
+    }
+
+    #[test]
+    fn reversed_range_should_be_empty() {
+        assert!(IntervalSet::from(RangeInclusive::new(3, 1)).is_empty());
+    }
+
+    #[test]
+    fn should_compute_len_and_bounds() {
+        let set = set(&[-3..=3, 10..=10]);
+        assert_eq!(set.len(), 8);
+        assert_eq!(set.bounds(), Some(-3..=10));
+        assert_eq!(IntervalSet::<i32>::new().bounds(), None);
+    }
+}
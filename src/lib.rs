@@ -3,6 +3,8 @@
 #[allow(unused_imports)]
 extern crate rstest;
 
+pub mod animation;
+pub mod cycle;
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -28,4 +30,10 @@ pub mod day22;
 pub mod day23;
 pub mod day24;
 pub mod day25;
+pub mod interval;
+pub mod math;
+pub mod netpbm;
+pub mod ranking;
+pub mod search;
 pub mod template;
+pub mod voxel;
@@ -0,0 +1,170 @@
+/// Greatest common divisor (`gcd(0, 0)` is 0)
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Least common multiple, or `None` if it overflows (`lcm(0, n)` is 0)
+pub fn lcm(a: u64, b: u64) -> Option<u64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// Greatest common divisor of all the values (0 if there is none)
+pub fn gcd_all(values: impl IntoIterator<Item = u64>) -> u64 {
+    values.into_iter().fold(0, gcd)
+}
+
+/// Least common multiple of all the values (1 if there is none), or `None` if it overflows
+pub fn lcm_all(values: impl IntoIterator<Item = u64>) -> Option<u64> {
+    values.into_iter().try_fold(1, lcm)
+}
+
+/// `base ^ exponent mod modulus`, by repeated squaring
+///
+/// Panics if `modulus` is 0.
+pub fn mod_pow(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let modulus = u128::from(modulus);
+    let mut base = u128::from(base) % modulus;
+    let mut result = 1 % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    result as u64
+}
+
+/// `x` such that `a * x ≡ 1 (mod modulus)`, or `None` if `a` and `modulus` are not coprime or `modulus` is 0
+pub fn mod_inv(a: u64, modulus: u64) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    let (g, x, _) = extended_gcd(i128::from(a), i128::from(modulus));
+    (g == 1).then(|| x.rem_euclid(i128::from(modulus)) as u64)
+}
+
+/// Smallest `x` satisfying `x ≡ residue (mod modulus)` for every `(residue, modulus)` pair,
+/// together with the lcm of the moduli, since every `x + k * lcm` is a solution as well
+///
+/// The moduli don't need to be coprime. Returns `None` if the congruences are incompatible,
+/// if a modulus is 0, or if the lcm of the moduli overflows.
+pub fn crt(congruences: impl IntoIterator<Item = (u64, u64)>) -> Option<(u64, u64)> {
+    congruences
+        .into_iter()
+        .try_fold((0, 1), |(x, m), (residue, modulus)| {
+            if modulus == 0 {
+                return None;
+            }
+            let (x, m) = (i128::from(x), i128::from(m));
+            let (residue, modulus) = (i128::from(residue), i128::from(modulus));
+            // x + m * k ≡ residue (mod modulus)  <=>  (m / g) * k ≡ (residue - x) / g (mod modulus / g)
+            let (g, inverse, _) = extended_gcd(m, modulus);
+            let difference = residue - x;
+            if difference % g != 0 {
+                return None;
+            }
+            let reduced = modulus / g;
+            let lcm = u64::try_from(m * reduced).ok()?;
+            // Both factors are below 2^64, so their product fits in a `u128`
+            let k = ((difference / g).rem_euclid(reduced) as u128
+                * inverse.rem_euclid(reduced) as u128
+                % reduced as u128) as i128;
+            let x = (x + m * k).rem_euclid(i128::from(lcm));
+            Some((x as u64, lcm))
+        })
+}
+
+/// `(g, x, y)` such that `a * x + b * y = g = gcd(a, b)`
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - a / b * y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    #[case(0, 0, 0)]
+    #[case(0, 7, 7)]
+    #[case(12, 18, 6)]
+    #[case(17, 5, 1)]
+    #[case(u64::MAX, u64::MAX, u64::MAX)]
+    fn should_compute_gcd(#[case] a: u64, #[case] b: u64, #[case] expected: u64) {
+        assert_eq!(gcd(a, b), expected);
+        assert_eq!(gcd(b, a), expected);
+    }
+
+    #[rstest]
+    #[case(&[], Some(1))]
+    #[case(&[4, 6], Some(12))]
+    #[case(&[23, 19, 13, 17], Some(96577))]
+    #[case(&[2, 4, 8, 3], Some(24))]
+    #[case(&[5, 0], Some(0))]
+    #[case(&[u64::MAX, 2], None)]
+    fn should_compute_lcm_of_all_values(#[case] values: &[u64], #[case] expected: Option<u64>) {
+        assert_eq!(lcm_all(values.iter().copied()), expected);
+    }
+
+    #[test]
+    fn should_compute_gcd_of_all_values() {
+        assert_eq!(gcd_all([]), 0);
+        assert_eq!(gcd_all([12, 18, 30]), 6);
+    }
+
+    #[rstest]
+    #[case(2, 10, 1000, 24)]
+    #[case(3, 0, 7, 1)]
+    #[case(5, 3, 1, 0)]
+    #[case(u64::MAX, 2, u64::MAX - 1, 1)]
+    fn should_compute_modular_power(
+        #[case] base: u64,
+        #[case] exponent: u64,
+        #[case] modulus: u64,
+        #[case] expected: u64,
+    ) {
+        assert_eq!(mod_pow(base, exponent, modulus), expected);
+    }
+
+    #[rstest]
+    #[case(3, 11, Some(4))]
+    #[case(10, 17, Some(12))]
+    #[case(6, 9, None)]
+    #[case(1, 0, None)]
+    #[case(0, 0, None)]
+    #[case(0, 1, Some(0))]
+    #[case(5, 1, Some(0))]
+    fn should_compute_modular_inverse(
+        #[case] a: u64,
+        #[case] modulus: u64,
+        #[case] expected: Option<u64>,
+    ) {
+        assert_eq!(mod_inv(a, modulus), expected);
+    }
+
+    #[rstest]
+    #[case(&[], Some((0, 1)))]
+    #[case(&[(2, 3), (3, 5), (2, 7)], Some((23, 105)))]
+    #[case(&[(3, 4), (1, 6)], Some((7, 12)))]
+    #[case(&[(1, 4), (2, 6)], None)]
+    #[case(&[(1, 0)], None)]
+    #[case(&[(1, 4294967291), (2, 4294967279)], Some((1537228665292936541, 18446743979220271189)))]
+    #[case(&[(1, 4294967291), (2, 4294967279), (0, 2)], None)]
+    fn should_solve_congruences(
+        #[case] congruences: &[(u64, u64)],
+        #[case] expected: Option<(u64, u64)>,
+    ) {
+        assert_eq!(crt(congruences.iter().copied()), expected);
+    }
+}
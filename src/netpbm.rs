@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::animation::Frame;
+
+/// Pixel of a Netpbm image, with 8 bits per channel
+pub trait Pixel: Copy {
+    /// Magic number of the binary format
+    const MAGIC: &'static str;
+    /// Default file extension
+    const EXTENSION: &'static str;
+
+    fn write_to(self, bytes: &mut Vec<u8>);
+}
+
+/// Grayscale pixel, from black (0) to white (255)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Gray(pub u8);
+
+/// Colour pixel
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Pixel for Gray {
+    const MAGIC: &'static str = "P5";
+    const EXTENSION: &'static str = "pgm";
+
+    fn write_to(self, bytes: &mut Vec<u8>) {
+        bytes.push(self.0);
+    }
+}
+
+impl Pixel for Rgb {
+    const MAGIC: &'static str = "P6";
+    const EXTENSION: &'static str = "ppm";
+
+    fn write_to(self, bytes: &mut Vec<u8>) {
+        bytes.extend([self.0, self.1, self.2]);
+    }
+}
+
+/// Colour of each cell of a grid
+pub trait ColorMap<T> {
+    type Pixel: Pixel;
+
+    fn color(&self, cell: T) -> Self::Pixel;
+}
+
+impl<T, P: Pixel, F: Fn(T) -> P> ColorMap<T> for F {
+    type Pixel = P;
+
+    fn color(&self, cell: T) -> P {
+        self(cell)
+    }
+}
+
+/// Maps the values from `min` (black) to `max` (white)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Grayscale {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl<T: Into<u32>> ColorMap<T> for Grayscale {
+    type Pixel = Gray;
+
+    fn color(&self, cell: T) -> Gray {
+        let value = cell.into().clamp(self.min, self.max) - self.min;
+        let range = (self.max - self.min).max(1);
+        Gray((u64::from(value) * 255 / u64::from(range)) as u8)
+    }
+}
+
+/// Colour of each character, for images of text renderings
+#[derive(Debug, Clone)]
+pub struct Palette<P> {
+    colors: HashMap<char, P>,
+    default: P,
+}
+
+impl<P: Pixel> Palette<P> {
+    /// Palette using the `default` colour for every character
+    pub fn new(default: P) -> Self {
+        Self {
+            colors: HashMap::new(),
+            default,
+        }
+    }
+
+    pub fn with(mut self, c: char, color: P) -> Self {
+        self.colors.insert(c, color);
+        self
+    }
+}
+
+impl<P: Pixel> ColorMap<char> for Palette<P> {
+    type Pixel = P;
+
+    fn color(&self, cell: char) -> P {
+        self.colors.get(&cell).copied().unwrap_or(self.default)
+    }
+}
+
+/// Image that can be written as a binary PGM (grayscale) or PPM (colour) file
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Image<P> {
+    width: usize,
+    height: usize,
+    /// Row major, first row on top
+    pixels: Vec<P>,
+}
+
+impl<P: Pixel> Image<P> {
+    /// Creates an image by calling `pixel` with the `(x, y)` of each pixel, `y` growing downward
+    pub fn from_fn(
+        width: usize,
+        height: usize,
+        mut pixel: impl FnMut((usize, usize)) -> P,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            pixels: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(&mut pixel)
+                .collect(),
+        }
+    }
+
+    /// Image with one pixel per character of the `frame`
+    pub fn from_frame(frame: &Frame, colors: &impl ColorMap<char, Pixel = P>) -> Self {
+        Self::from_fn(frame.width(), frame.height(), |pos| {
+            colors.color(frame.get(pos).unwrap())
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (x, y): (usize, usize)) -> Option<P> {
+        if x >= self.width {
+            return None;
+        }
+        self.pixels.get(y * self.width + x).copied()
+    }
+
+    /// Enlarges the image so that each pixel becomes a square of `factor` x `factor` pixels
+    pub fn scaled(&self, factor: usize) -> Self {
+        Self::from_fn(self.width * factor, self.height * factor, |(x, y)| {
+            self.pixels[(y / factor) * self.width + x / factor]
+        })
+    }
+
+    /// Writes the image in the binary Netpbm format
+    pub fn write(&self, mut out: impl Write) -> io::Result<()> {
+        let mut bytes = format!("{}\n{} {}\n255\n", P::MAGIC, self.width, self.height).into_bytes();
+        self.pixels.iter().for_each(|p| p.write_to(&mut bytes));
+        out.write_all(&bytes)
+    }
+}
+
+/// Writes each image in its own file (`{name}-00000.pgm`, `{name}-00001.pgm`, ...) in `dir`,
+/// with the `.ppm` extension instead for colour images
+///
+/// Returns the number of written images.
+pub fn write_sequence<P: Pixel>(
+    images: impl IntoIterator<Item = Image<P>>,
+    dir: impl AsRef<Path>,
+    name: &str,
+) -> io::Result<usize> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let mut count = 0;
+    for image in images {
+        let path = dir.join(format!("{name}-{count:05}.{}", P::EXTENSION));
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        image.write(&mut out)?;
+        out.flush()?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_write_pgm() {
+        let image = Image::from_fn(3, 2, |(x, y)| Gray((x + 10 * y) as u8));
+        let mut bytes = Vec::new();
+        image.write(&mut bytes).unwrap();
+        assert_eq!(bytes, b"P5\n3 2\n255\n\x00\x01\x02\x0a\x0b\x0c");
+    }
+
+    #[test]
+    fn should_write_ppm() {
+        let image = Image::from_fn(2, 1, |(x, _)| Rgb(x as u8, 2, 3));
+        let mut bytes = Vec::new();
+        image.write(&mut bytes).unwrap();
+        assert_eq!(bytes, b"P6\n2 1\n255\n\x00\x02\x03\x01\x02\x03");
+    }
+
+    #[test]
+    fn should_scale_image() {
+        let image = Image::from_fn(2, 1, |(x, _)| Gray(x as u8)).scaled(3);
+        assert_eq!((image.width(), image.height()), (6, 3));
+        assert_eq!(image.get((2, 2)), Some(Gray(0)));
+        assert_eq!(image.get((3, 0)), Some(Gray(1)));
+        assert_eq!(image.get((6, 0)), None);
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(3, 0)]
+    #[case(4, 51)]
+    #[case(8, 255)]
+    #[case(42, 255)]
+    fn should_map_values_to_grayscale(#[case] value: u8, #[case] expected: u8) {
+        assert_eq!(Grayscale { min: 3, max: 8 }.color(value), Gray(expected));
+    }
+
+    #[rstest]
+    #[case(u32::MAX / 2, 127)]
+    #[case(u32::MAX - 1, 254)]
+    #[case(u32::MAX, 255)]
+    fn should_map_wide_ranges_to_grayscale(#[case] value: u32, #[case] expected: u8) {
+        let colors = Grayscale {
+            min: 0,
+            max: u32::MAX,
+        };
+        assert_eq!(colors.color(value), Gray(expected));
+    }
+
+    #[test]
+    fn should_map_frame_characters() {
+        let frame = Frame::from_fn(3, 1, |(col, _)| ['#', '.', '?'][col]);
+        let palette = Palette::new(Rgb(255, 0, 0)).with('#', Rgb(0, 0, 0));
+        let image = Image::from_frame(&frame, &palette.with('.', Rgb(255, 255, 255)));
+        assert_eq!(
+            image.pixels,
+            vec![Rgb(0, 0, 0), Rgb(255, 255, 255), Rgb(255, 0, 0)]
+        );
+        let inverted = Image::from_frame(&frame, &|c| Gray(if c == '#' { 255 } else { 0 }));
+        assert_eq!(inverted.get((0, 0)), Some(Gray(255)));
+    }
+
+    #[test]
+    fn should_write_image_sequence() {
+        let dir = std::env::temp_dir().join(format!("aoc-netpbm-{}", std::process::id()));
+        let images = (0..3).map(|i| Image::from_fn(1, 1, |_| Gray(i)));
+        let count = write_sequence(images, &dir, "step").unwrap();
+        let last = fs::read(dir.join("step-00002.pgm")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(last, b"P5\n1 1\n255\n\x02");
+    }
+}
@@ -0,0 +1,50 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// The `k` greatest items, sorted in decreasing order
+///
+/// Only `k + 1` items are kept in memory at once.
+pub fn top_k<T: Ord>(items: impl IntoIterator<Item = T>, k: usize) -> Vec<T> {
+    let mut top = BinaryHeap::with_capacity(k + 1);
+    for item in items {
+        top.push(Reverse(item));
+        if top.len() > k {
+            top.pop();
+        }
+    }
+    top.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(item)| item)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest]
+    #[case(&[], 3, &[])]
+    #[case(&[4, 1, 3], 0, &[])]
+    #[case(&[4, 1, 3], 2, &[4, 3])]
+    #[case(&[4, 1, 3], 5, &[4, 3, 1])]
+    #[case(&[2, 5, 2, 5, 1], 3, &[5, 5, 2])]
+    fn should_keep_greatest_items(
+        #[case] items: &[u32],
+        #[case] k: usize,
+        #[case] expected: &[u32],
+    ) {
+        assert_eq!(top_k(items.iter().copied(), k), expected);
+    }
+
+    #[test]
+    fn ties_can_be_broken_by_reversed_index() {
+        let items = [3, 7, 7, 1]
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (v, Reverse(i)));
+        let top: Vec<_> = top_k(items, 2)
+            .into_iter()
+            .map(|(_, Reverse(i))| i)
+            .collect();
+        assert_eq!(top, vec![1, 2]);
+    }
+}
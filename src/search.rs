@@ -0,0 +1,300 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+};
+
+/// Graph of states, where each state leads to its neighbours at some cost
+///
+/// States are compared and hashed to detect the ones that were already visited,
+/// so they should contain only what matters to the rest of the search (for example `(position, time % period)`).
+pub trait Neighbours<S> {
+    type Iter: IntoIterator<Item = (S, u64)>;
+
+    /// States reachable in one step from `state`, with the cost of each step
+    fn neighbours(&self, state: &S) -> Self::Iter;
+}
+
+impl<S, I, F> Neighbours<S> for F
+where
+    F: Fn(&S) -> I,
+    I: IntoIterator<Item = (S, u64)>,
+{
+    type Iter = I;
+
+    fn neighbours(&self, state: &S) -> I {
+        self(state)
+    }
+}
+
+/// Outcome of a search: the visited states, their cost, and how they were reached
+#[derive(Debug, Clone)]
+pub struct Search<S> {
+    /// Cost of the best known path to each visited state, and the state it comes from
+    visited: HashMap<S, (u64, Option<S>)>,
+    goal: Option<S>,
+}
+
+impl<S: Clone + Eq + Hash> Search<S> {
+    fn new() -> Self {
+        Self {
+            visited: HashMap::new(),
+            goal: None,
+        }
+    }
+
+    /// First state found that satisfied the goal predicate
+    pub fn goal(&self) -> Option<&S> {
+        self.goal.as_ref()
+    }
+
+    /// Cost of the path to the goal
+    pub fn goal_cost(&self) -> Option<u64> {
+        self.cost(self.goal.as_ref()?)
+    }
+
+    /// Cost of the best path found to `state`
+    ///
+    /// It is only guaranteed to be optimal for states that were expanded before the search stopped.
+    pub fn cost(&self, state: &S) -> Option<u64> {
+        self.visited.get(state).map(|(cost, _)| *cost)
+    }
+
+    /// States of the path from one of the sources to `state`, both included
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        let mut path = vec![state.clone()];
+        let mut parent = &self.visited.get(state)?.1;
+        while let Some(state) = parent {
+            path.push(state.clone());
+            parent = &self.visited[state].1;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// States of the path to the goal
+    pub fn goal_path(&self) -> Option<Vec<S>> {
+        self.path_to(self.goal.as_ref()?)
+    }
+
+    /// Every state reached by the search
+    pub fn visited(&self) -> impl Iterator<Item = &S> {
+        self.visited.keys()
+    }
+
+    /// Records `state` if it was not visited yet, or if it is reached at a lower cost
+    fn improve(&mut self, state: &S, cost: u64, parent: Option<&S>) -> bool {
+        if matches!(self.visited.get(state), Some((known, _)) if *known <= cost) {
+            return false;
+        }
+        self.visited.insert(state.clone(), (cost, parent.cloned()));
+        true
+    }
+}
+
+/// Breadth-first search from the `sources`, until a state satisfying `is_goal` is found
+///
+/// Step costs are ignored: the cost of a state is the number of steps to reach it.
+pub fn bfs<S, G>(
+    graph: &G,
+    sources: impl IntoIterator<Item = S>,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Search<S>
+where
+    S: Clone + Eq + Hash,
+    G: Neighbours<S>,
+{
+    let mut search = Search::new();
+    let mut queue = VecDeque::new();
+    for source in sources {
+        if search.improve(&source, 0, None) {
+            queue.push_back((source, 0));
+        }
+    }
+    while let Some((state, steps)) = queue.pop_front() {
+        if is_goal(&state) {
+            search.goal = Some(state);
+            break;
+        }
+        for (next, _) in graph.neighbours(&state) {
+            if !search.visited.contains_key(&next) {
+                search.improve(&next, steps + 1, Some(&state));
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    search
+}
+
+/// Cheapest paths from the `sources`, until a state satisfying `is_goal` is found
+pub fn dijkstra<S, G>(
+    graph: &G,
+    sources: impl IntoIterator<Item = S>,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Search<S>
+where
+    S: Clone + Eq + Hash,
+    G: Neighbours<S>,
+{
+    astar(graph, sources, is_goal, |_| 0)
+}
+
+/// Cheapest paths from the `sources`, until a state satisfying `is_goal` is found,
+/// exploring first the states that look closer to the goal according to the `heuristic`
+///
+/// The `heuristic` must never overestimate the cost to reach a goal, otherwise the path found may not be the cheapest.
+pub fn astar<S, G>(
+    graph: &G,
+    sources: impl IntoIterator<Item = S>,
+    mut is_goal: impl FnMut(&S) -> bool,
+    mut heuristic: impl FnMut(&S) -> u64,
+) -> Search<S>
+where
+    S: Clone + Eq + Hash,
+    G: Neighbours<S>,
+{
+    let mut search = Search::new();
+    // The heap only contains indices into `states`, so that states don't need to be ordered
+    let mut states = Vec::new();
+    let mut queue = BinaryHeap::new();
+    for source in sources {
+        if search.improve(&source, 0, None) {
+            queue.push(Reverse((heuristic(&source), 0, states.len())));
+            states.push(source);
+        }
+    }
+    while let Some(Reverse((_, cost, index))) = queue.pop() {
+        let state = states[index].clone();
+        if search.cost(&state) != Some(cost) {
+            continue;
+        }
+        if is_goal(&state) {
+            search.goal = Some(state);
+            break;
+        }
+        for (next, step) in graph.neighbours(&state) {
+            let cost = cost + step;
+            if search.improve(&next, cost, Some(&state)) {
+                queue.push(Reverse((cost + heuristic(&next), cost, states.len())));
+                states.push(next);
+            }
+        }
+    }
+    search
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Edges = Vec<((i32, i32), u64)>;
+
+    /// Walls are `#`, every other cell can be walked on
+    fn grid(maze: &str) -> impl Fn(&(i32, i32)) -> Edges + '_ {
+        let rows: Vec<&[u8]> = maze.lines().map(str::as_bytes).collect();
+        move |&(x, y)| {
+            [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                .into_iter()
+                .filter(|&(x, y)| {
+                    let cell = rows.get(y as usize).and_then(|row| row.get(x as usize));
+                    x >= 0 && y >= 0 && matches!(cell, Some(c) if *c != b'#')
+                })
+                .map(|p| (p, 1))
+                .collect()
+        }
+    }
+
+    const MAZE: &str = "\
+...#....
+.#.#.##.
+.#...#..
+.####.#.
+......#.";
+
+    #[test]
+    fn bfs_should_find_shortest_path() {
+        let search = bfs(&grid(MAZE), [(0, 0)], |p| *p == (7, 4));
+        assert_eq!(search.goal(), Some(&(7, 4)));
+        assert_eq!(search.goal_cost(), Some(15));
+        let path = search.goal_path().unwrap();
+        assert_eq!(path.len(), 16);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert!(path
+            .windows(2)
+            .all(|s| (s[0].0 - s[1].0).abs() + (s[0].1 - s[1].1).abs() == 1));
+    }
+
+    #[test]
+    fn bfs_should_explore_everything_without_goal() {
+        let search = bfs(&grid(MAZE), [(0, 0)], |_| false);
+        assert_eq!(search.goal(), None);
+        assert_eq!(search.visited().count(), 27);
+        assert_eq!(search.cost(&(7, 0)), Some(11));
+        assert_eq!(search.cost(&(3, 0)), None);
+    }
+
+    #[test]
+    fn should_search_from_multiple_sources() {
+        let search = bfs(&grid(MAZE), [(0, 0), (7, 0)], |p| *p == (7, 4));
+        assert_eq!(search.goal_cost(), Some(4));
+        assert_eq!(search.goal_path().unwrap().first(), Some(&(7, 0)));
+        let search = dijkstra(&grid(MAZE), [(0, 0), (7, 0)], |p| *p == (0, 4));
+        assert_eq!(search.goal_cost(), Some(4));
+    }
+
+    #[test]
+    fn dijkstra_should_prefer_cheaper_longer_paths() {
+        // 0 -> 3 costs 10 directly, or 3 through 1 and 2
+        let graph = |n: &u32| -> Vec<(u32, u64)> {
+            match n {
+                0 => vec![(3, 10), (1, 1)],
+                1 => vec![(2, 1)],
+                2 => vec![(3, 1)],
+                _ => vec![],
+            }
+        };
+        let search = dijkstra(&graph, [0], |n| *n == 3);
+        assert_eq!(search.goal_cost(), Some(3));
+        assert_eq!(search.goal_path(), Some(vec![0, 1, 2, 3]));
+        assert_eq!(bfs(&graph, [0], |n| *n == 3).goal_cost(), Some(1));
+    }
+
+    #[test]
+    fn astar_should_find_same_cost_as_dijkstra() {
+        let graph = grid(MAZE);
+        let goal = (7, 4);
+        let manhattan = |&(x, y): &(i32, i32)| ((goal.0 - x).abs() + (goal.1 - y).abs()) as u64;
+        let astar = astar(&graph, [(0, 0)], |p| *p == goal, manhattan);
+        let dijkstra = dijkstra(&graph, [(0, 0)], |p| *p == goal);
+        assert_eq!(astar.goal_cost(), Some(15));
+        assert_eq!(dijkstra.goal_cost(), Some(15));
+        assert!(astar.visited().count() <= dijkstra.visited().count());
+    }
+
+    #[test]
+    fn should_search_time_dependent_states() {
+        // A corridor where the middle cell is blocked at even times: waiting is allowed
+        let period = 2;
+        let graph = |&(x, t): &(i32, u32)| -> Vec<((i32, u32), u64)> {
+            [x - 1, x, x + 1]
+                .into_iter()
+                .filter(|x| (0..3).contains(x))
+                .map(|x| (x, (t + 1) % period))
+                .filter(|&(x, t)| x != 1 || t % 2 == 1)
+                .map(|state| (state, 1))
+                .collect()
+        };
+        let search = bfs(&graph, [(0, 0)], |(x, _)| *x == 2);
+        assert_eq!(search.goal_cost(), Some(2));
+        let search = bfs(&graph, [(0, 1)], |(x, _)| *x == 2);
+        assert_eq!(search.goal_cost(), Some(3));
+        assert!(search.visited().count() <= 6);
+    }
+
+    #[test]
+    fn source_can_be_goal() {
+        let search = dijkstra(&grid(MAZE), [(0, 0)], |_| true);
+        assert_eq!(search.goal_path(), Some(vec![(0, 0)]));
+        assert_eq!(search.goal_cost(), Some(0));
+    }
+}
@@ -0,0 +1,474 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+pub type Point = (i32, i32, i32);
+
+/// The 6 voxels sharing a face with the given one
+pub fn neighbours((x, y, z): Point) -> [Point; 6] {
+    [
+        (x - 1, y, z),
+        (x + 1, y, z),
+        (x, y - 1, z),
+        (x, y + 1, z),
+        (x, y, z - 1),
+        (x, y, z + 1),
+    ]
+}
+
+fn point([x, y, z]: [i32; 3]) -> Point {
+    (x, y, z)
+}
+
+/// Set of voxels, stored as a dense array covering their bounding box
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct VoxelGrid {
+    /// Smallest coordinates covered by `cells`
+    min: Point,
+    size: (usize, usize, usize),
+    /// Indexed by `x`, then `y`, then `z`
+    cells: Vec<bool>,
+    len: usize,
+    /// Box requested by [`VoxelGrid::with_bounds`] or reached by an insertion,
+    /// which may be smaller than the one covered by `cells`
+    covered: Option<(Point, Point)>,
+}
+
+impl VoxelGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty grid covering every voxel from `min` to `max` (included)
+    pub fn with_bounds(min: Point, max: Point) -> Self {
+        let size = (
+            (max.0 - min.0 + 1).max(0) as usize,
+            (max.1 - min.1 + 1).max(0) as usize,
+            (max.2 - min.2 + 1).max(0) as usize,
+        );
+        let cells = vec![false; size.0 * size.1 * size.2];
+        Self {
+            min,
+            size,
+            covered: (!cells.is_empty()).then_some((min, max)),
+            cells,
+            len: 0,
+        }
+    }
+
+    /// Adds the voxel, extending the covered bounds if necessary
+    ///
+    /// Returns false if the voxel was already present.
+    pub fn insert(&mut self, voxel: Point) -> bool {
+        let index = match self.index(voxel) {
+            Some(index) => index,
+            None => {
+                self.extend_to(voxel);
+                self.index(voxel).unwrap()
+            }
+        };
+        self.covered = Some(match self.covered {
+            Some((min, max)) => (
+                (min.0.min(voxel.0), min.1.min(voxel.1), min.2.min(voxel.2)),
+                (max.0.max(voxel.0), max.1.max(voxel.1), max.2.max(voxel.2)),
+            ),
+            None => (voxel, voxel),
+        });
+        if self.cells[index] {
+            return false;
+        }
+        self.cells[index] = true;
+        self.len += 1;
+        true
+    }
+
+    pub fn contains(&self, voxel: Point) -> bool {
+        self.index(voxel).map_or(false, |i| self.cells[i])
+    }
+
+    /// Number of voxels in the grid
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Smallest and biggest coordinates of the covered box
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        self.covered
+    }
+
+    /// Smallest and biggest coordinates of the allocated cells
+    fn allocated(&self) -> Option<(Point, Point)> {
+        if self.cells.is_empty() {
+            return None;
+        }
+        let (x, y, z) = self.min;
+        let (w, h, d) = self.size;
+        Some((
+            self.min,
+            (x + w as i32 - 1, y + h as i32 - 1, z + d as i32 - 1),
+        ))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Point> + '_ {
+        let (_, h, d) = self.size;
+        let (x, y, z) = self.min;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, present)| **present)
+            .map(move |(i, _)| {
+                (
+                    x + (i / (h * d)) as i32,
+                    y + (i / d % h) as i32,
+                    z + (i % d) as i32,
+                )
+            })
+    }
+
+    /// Number of voxel faces that are not shared with another voxel
+    pub fn surface_area(&self) -> usize {
+        self.iter()
+            .flat_map(neighbours)
+            .filter(|n| !self.contains(*n))
+            .count()
+    }
+
+    /// Mesh of the outer faces, where adjacent coplanar faces are merged into rectangles
+    pub fn surface(&self) -> Mesh {
+        let mut quads = Vec::new();
+        let Some((min, max)) = self.bounds() else {
+            return Mesh { quads };
+        };
+        let (min, max) = ([min.0, min.1, min.2], [max.0, max.1, max.2]);
+        for axis in 0..3 {
+            let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+            let (width, height) = (
+                (max[u] - min[u] + 1) as usize,
+                (max[v] - min[v] + 1) as usize,
+            );
+            for direction in [-1, 1] {
+                for layer in min[axis]..=max[axis] {
+                    let exposed: Vec<bool> = (0..width * height)
+                        .map(|i| {
+                            let mut voxel = [0; 3];
+                            voxel[axis] = layer;
+                            voxel[u] = min[u] + (i % width) as i32;
+                            voxel[v] = min[v] + (i / width) as i32;
+                            let mut neighbour = voxel;
+                            neighbour[axis] += direction;
+                            self.contains(point(voxel)) && !self.contains(point(neighbour))
+                        })
+                        .collect();
+                    let plane = if direction > 0 { layer + 1 } else { layer };
+                    for (u0, v0, u1, v1) in merge_rectangles(exposed, width) {
+                        let corner = |cu: usize, cv: usize| {
+                            let mut point = [0; 3];
+                            point[axis] = plane;
+                            point[u] = min[u] + cu as i32;
+                            point[v] = min[v] + cv as i32;
+                            point
+                        };
+                        let mut corners = [
+                            corner(u0, v0),
+                            corner(u1, v0),
+                            corner(u1, v1),
+                            corner(u0, v1),
+                        ];
+                        if direction < 0 {
+                            corners.reverse();
+                        }
+                        let mut normal = [0; 3];
+                        normal[axis] = direction;
+                        quads.push(Quad { normal, corners });
+                    }
+                }
+            }
+        }
+        Mesh { quads }
+    }
+
+    fn index(&self, (x, y, z): Point) -> Option<usize> {
+        let (w, h, d) = self.size;
+        let x = usize::try_from(x - self.min.0).ok().filter(|x| *x < w)?;
+        let y = usize::try_from(y - self.min.1).ok().filter(|y| *y < h)?;
+        let z = usize::try_from(z - self.min.2).ok().filter(|z| *z < d)?;
+        Some((x * h + y) * d + z)
+    }
+
+    /// Extends the covered bounds to include the voxel
+    ///
+    /// Each axis that must grow is at least doubled, so that inserting voxels one by one
+    /// only copies the grid a logarithmic number of times.
+    fn extend_to(&mut self, voxel: Point) {
+        let (min, max) = match self.allocated() {
+            Some((min, max)) => {
+                let (w, h, d) = self.size;
+                let grow = |value: i32, min: i32, max: i32, size: usize| {
+                    let size = i32::try_from(size).unwrap_or(i32::MAX);
+                    if value < min {
+                        (value.min(min.saturating_sub(size)), max)
+                    } else if value > max {
+                        (min, value.max(max.saturating_add(size)))
+                    } else {
+                        (min, max)
+                    }
+                };
+                let (x, y, z) = (
+                    grow(voxel.0, min.0, max.0, w),
+                    grow(voxel.1, min.1, max.1, h),
+                    grow(voxel.2, min.2, max.2, d),
+                );
+                ((x.0, y.0, z.0), (x.1, y.1, z.1))
+            }
+            None => (voxel, voxel),
+        };
+        let mut extended = Self::with_bounds(min, max);
+        self.iter().for_each(|voxel| {
+            extended.insert(voxel);
+        });
+        extended.covered = self.covered;
+        *self = extended;
+    }
+}
+
+impl FromIterator<Point> for VoxelGrid {
+    fn from_iter<T: IntoIterator<Item = Point>>(iter: T) -> Self {
+        let voxels: Vec<Point> = iter.into_iter().collect();
+        let Some(&first) = voxels.first() else {
+            return Self::new();
+        };
+        let (min, max) = voxels
+            .iter()
+            .fold((first, first), |(min, max), &(x, y, z)| {
+                (
+                    (min.0.min(x), min.1.min(y), min.2.min(z)),
+                    (max.0.max(x), max.1.max(y), max.2.max(z)),
+                )
+            });
+        let mut grid = Self::with_bounds(min, max);
+        voxels.into_iter().for_each(|voxel| {
+            grid.insert(voxel);
+        });
+        grid
+    }
+}
+
+/// Greedily covers the `true` cells of a row-major mask with rectangles
+///
+/// Each rectangle is returned as `(u0, v0, u1, v1)`, with the end bounds excluded.
+fn merge_rectangles(
+    mut mask: Vec<bool>,
+    width: usize,
+) -> impl Iterator<Item = (usize, usize, usize, usize)> {
+    let height = mask.len().checked_div(width).unwrap_or_default();
+    let mut rectangles = Vec::new();
+    for v0 in 0..height {
+        let mut u0 = 0;
+        while u0 < width {
+            if !mask[v0 * width + u0] {
+                u0 += 1;
+                continue;
+            }
+            let u1 = (u0..width)
+                .find(|&u| !mask[v0 * width + u])
+                .unwrap_or(width);
+            let v1 = (v0 + 1..height)
+                .find(|&v| !(u0..u1).all(|u| mask[v * width + u]))
+                .unwrap_or(height);
+            for v in v0..v1 {
+                mask[v * width + u0..v * width + u1].fill(false);
+            }
+            rectangles.push((u0, v0, u1, v1));
+            u0 = u1;
+        }
+    }
+    rectangles.into_iter()
+}
+
+/// Surface made of rectangular faces
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Mesh {
+    quads: Vec<Quad>,
+}
+
+/// Rectangular face, with its corners in counter-clockwise order when seen from the outside
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Quad {
+    pub normal: [i32; 3],
+    pub corners: [[i32; 3]; 4],
+}
+
+impl Quad {
+    /// Number of unit squares covered by the face
+    pub fn area(&self) -> u64 {
+        let [a, _, c, _] = self.corners;
+        (0..3)
+            .filter(|&i| self.normal[i] == 0)
+            .map(|i| a[i].abs_diff(c[i]) as u64)
+            .product()
+    }
+}
+
+impl Mesh {
+    pub fn quads(&self) -> &[Quad] {
+        &self.quads
+    }
+
+    /// Number of unit squares covered by the faces
+    pub fn area(&self) -> u64 {
+        self.quads.iter().map(Quad::area).sum()
+    }
+
+    /// Writes the mesh as an ASCII STL solid, with two triangles per face
+    pub fn write_stl(&self, name: &str, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "solid {name}")?;
+        for Quad { normal, corners } in &self.quads {
+            let [a, b, c, d] = corners;
+            for triangle in [[a, b, c], [a, c, d]] {
+                writeln!(
+                    out,
+                    "  facet normal {} {} {}",
+                    normal[0], normal[1], normal[2]
+                )?;
+                writeln!(out, "    outer loop")?;
+                for [x, y, z] in triangle {
+                    writeln!(out, "      vertex {x} {y} {z}")?;
+                }
+                writeln!(out, "    endloop")?;
+                writeln!(out, "  endfacet")?;
+            }
+        }
+        writeln!(out, "endsolid {name}")
+    }
+
+    /// Writes the mesh as a Wavefront OBJ file, with shared vertices and one quad per face
+    pub fn write_obj(&self, mut out: impl Write) -> io::Result<()> {
+        let mut indices = HashMap::new();
+        let mut faces = Vec::with_capacity(self.quads.len());
+        for quad in &self.quads {
+            let mut face = [0; 4];
+            for (index, corner) in face.iter_mut().zip(quad.corners) {
+                *index = match indices.get(&corner) {
+                    Some(&index) => index,
+                    None => {
+                        let [x, y, z] = corner;
+                        writeln!(out, "v {x} {y} {z}")?;
+                        indices.insert(corner, indices.len() + 1);
+                        indices.len()
+                    }
+                };
+            }
+            faces.push(face);
+        }
+        for [a, b, c, d] in faces {
+            writeln!(out, "f {a} {b} {c} {d}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_store_negative_coordinates() {
+        let mut grid = VoxelGrid::new();
+        assert!(grid.insert((0, 0, 0)));
+        assert!(grid.insert((-3, 2, -1)));
+        assert!(!grid.insert((-3, 2, -1)));
+        assert!(grid.insert((1, -5, 4)));
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid.bounds(), Some(((-3, -5, -1), (1, 2, 4))));
+        assert!(grid.contains((-3, 2, -1)));
+        assert!(!grid.contains((-3, 2, 0)));
+        assert!(!grid.contains((100, 0, 0)));
+        let mut voxels: Vec<_> = grid.iter().collect();
+        voxels.sort_unstable();
+        assert_eq!(voxels, vec![(-3, 2, -1), (0, 0, 0), (1, -5, 4)]);
+    }
+
+    #[test]
+    fn inserting_voxels_one_by_one_should_grow_geometrically() {
+        let mut grid = VoxelGrid::new();
+        for x in 0..1000 {
+            assert!(grid.insert((x, 0, 0)));
+            assert!(grid.insert((0, -x - 1, 0)));
+        }
+        assert_eq!(grid.len(), 2000);
+        assert_eq!(grid.bounds(), Some(((0, -1000, 0), (999, 0, 0))));
+        assert!(grid.cells.len() <= 4 * 1001 * 1001);
+        assert!((0..1000).all(|x| grid.contains((x, 0, 0)) && grid.contains((0, -x - 1, 0))));
+    }
+
+    #[test]
+    fn should_collect_voxels_within_bounds() {
+        let grid: VoxelGrid = [(1, 1, 1), (2, 1, 1), (1, 1, 1)].into_iter().collect();
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.bounds(), Some(((1, 1, 1), (2, 1, 1))));
+        assert_eq!(VoxelGrid::new().bounds(), None);
+    }
+
+    #[rstest]
+    #[case(&[(0, 0, 0)], 6, 6)]
+    #[case(&[(0, 0, 0), (1, 0, 0)], 10, 6)]
+    #[case(&[(0, 0, 0), (2, 0, 0)], 12, 12)]
+    #[case(&[(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0)], 16, 6)]
+    #[case(&[(0, 0, 0), (1, 0, 0), (0, 1, 0)], 14, 10)]
+    fn should_merge_coplanar_faces(
+        #[case] voxels: &[Point],
+        #[case] expected_area: usize,
+        #[case] expected_faces: usize,
+    ) {
+        let grid: VoxelGrid = voxels.iter().copied().collect();
+        let mesh = grid.surface();
+        assert_eq!(grid.surface_area(), expected_area);
+        assert_eq!(mesh.area(), expected_area as u64);
+        assert_eq!(mesh.quads().len(), expected_faces);
+    }
+
+    #[test]
+    fn faces_should_be_counter_clockwise_from_outside() {
+        let mesh: Mesh = [(0, 0, 0)].into_iter().collect::<VoxelGrid>().surface();
+        for Quad { normal, corners } in mesh.quads() {
+            let [a, b, c, _] = corners;
+            let (ab, ac) = (
+                [b[0] - a[0], b[1] - a[1], b[2] - a[2]],
+                [c[0] - a[0], c[1] - a[1], c[2] - a[2]],
+            );
+            let cross = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            assert_eq!(&cross, normal);
+        }
+    }
+
+    #[test]
+    fn should_write_stl() {
+        let mesh = [(0, 0, 0)].into_iter().collect::<VoxelGrid>().surface();
+        let mut stl = Vec::new();
+        mesh.write_stl("cube", &mut stl).unwrap();
+        let stl = String::from_utf8(stl).unwrap();
+        assert!(stl.starts_with("solid cube\n"));
+        assert!(stl.ends_with("endsolid cube\n"));
+        assert_eq!(stl.matches("facet normal").count(), 12);
+        assert_eq!(stl.matches("vertex").count(), 36);
+    }
+
+    #[test]
+    fn should_write_obj_with_shared_vertices() {
+        let mesh = [(0, 0, 0)].into_iter().collect::<VoxelGrid>().surface();
+        let mut obj = Vec::new();
+        mesh.write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 8);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("f ")).count(), 6);
+    }
+}